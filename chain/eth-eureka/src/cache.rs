@@ -0,0 +1,263 @@
+//! Cache for contract addresses derived from the `ICS26Router`
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    primitives::Address,
+    providers::{Provider, RootProvider},
+    rpc::types::Filter,
+    sol,
+    sol_types::SolEvent,
+    transports::BoxTransport,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::error::ChainModuleError;
+
+sol! {
+    /// Emitted by ERC1967 proxies when their implementation is upgraded.
+    #[allow(missing_docs)]
+    event Upgraded(address indexed implementation);
+}
+
+/// The configuration for the [`AddressCache`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct AddressCacheConfig {
+    /// How long a cached address stays valid, in seconds. Cached addresses never expire if
+    /// this is not set.
+    pub ttl_secs: Option<u64>,
+    /// How long the cached light client address of a client stays valid, in seconds. Clients
+    /// can be re-pointed or migrated on the ICS02 client without any upgrade, so these always
+    /// expire, at the latest after `ttl_secs`.
+    pub client_ttl_secs: u64,
+    /// How often to poll for `Upgraded` events emitted by the router, the IBC store and the
+    /// ICS02 client, in seconds. All cached addresses are dropped when an upgrade is detected.
+    /// Upgrades are not watched if this is not set.
+    pub upgrade_poll_interval_secs: Option<u64>,
+}
+
+impl Default for AddressCacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: None,
+            client_ttl_secs: 60,
+            upgrade_poll_interval_secs: None,
+        }
+    }
+}
+
+/// The addresses that are derived from the `ICS26Router`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CachedAddress {
+    /// `ICS26Router.IBC_STORE()`
+    IbcStore,
+    /// `ICS26Router.ICS02_CLIENT()`
+    Ics02Client,
+    /// `ICS02Client.getClient(client_id)`
+    LightClient(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    address: Address,
+    fetched_at: Instant,
+}
+
+/// Caches addresses derived from the `ICS26Router` so that they are not re-queried on every
+/// state query
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct AddressCache {
+    ttl: Option<Duration>,
+    client_ttl: Duration,
+    entries: Arc<RwLock<HashMap<CachedAddress, Entry>>>,
+}
+
+impl AddressCache {
+    /// Create a new, empty cache.
+    #[must_use]
+    pub fn new(config: &AddressCacheConfig) -> Self {
+        Self {
+            ttl: config.ttl_secs.map(Duration::from_secs),
+            client_ttl: Duration::from_secs(config.client_ttl_secs),
+            entries: Arc::default(),
+        }
+    }
+
+    /// Get a cached address, or fetch and cache it if it is missing or expired.
+    /// # Errors
+    /// Returns an error if `fetch` fails.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub async fn get_or_fetch<F, Fut>(
+        &self,
+        key: CachedAddress,
+        fetch: F,
+    ) -> Result<Address, ChainModuleError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Address, ChainModuleError>>,
+    {
+        if let Some(address) = self.get(&key) {
+            return Ok(address);
+        }
+
+        let address = fetch().await?;
+
+        self.entries.write().expect("lock is poisoned").insert(
+            key,
+            Entry {
+                address,
+                fetched_at: Instant::now(),
+            },
+        );
+
+        Ok(address)
+    }
+
+    fn get(&self, key: &CachedAddress) -> Option<Address> {
+        let entries = self.entries.read().expect("lock is poisoned");
+        let entry = entries.get(key)?;
+
+        let ttl = match key {
+            CachedAddress::LightClient(_) => Some(
+                self.ttl
+                    .map_or(self.client_ttl, |ttl| ttl.min(self.client_ttl)),
+            ),
+            CachedAddress::IbcStore | CachedAddress::Ics02Client => self.ttl,
+        };
+
+        match ttl {
+            Some(ttl) if entry.fetched_at.elapsed() >= ttl => None,
+            _ => Some(entry.address),
+        }
+    }
+
+    /// Drop all cached addresses.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub fn clear(&self) {
+        self.entries.write().expect("lock is poisoned").clear();
+    }
+
+    /// Spawn a task that clears the cache whenever one of `contracts` emits an `Upgraded`
+    /// event, polling every `poll_interval`.
+    pub fn watch_upgrades(
+        &self,
+        provider: RootProvider<BoxTransport>,
+        contracts: Vec<Address>,
+        poll_interval: Duration,
+    ) {
+        let cache = self.clone();
+        let filter = Filter::new()
+            .address(contracts)
+            .event_signature(Upgraded::SIGNATURE_HASH);
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+            let mut from_block = None;
+
+            loop {
+                interval.tick().await;
+
+                let latest_block = match provider.get_block_number().await {
+                    Ok(latest_block) => latest_block,
+                    Err(err) => {
+                        warn!(%err, "unable to fetch the latest block number");
+                        continue;
+                    }
+                };
+                let from = from_block.unwrap_or(latest_block);
+                if latest_block < from {
+                    continue;
+                }
+
+                let filter = filter.clone().from_block(from).to_block(latest_block);
+                match provider.get_logs(&filter).await {
+                    Ok(logs) if !logs.is_empty() => {
+                        info!(
+                            upgrades = logs.len(),
+                            "contract upgrade detected, clearing the address cache"
+                        );
+                        cache.clear();
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        warn!(%err, "unable to fetch upgrade events");
+                        continue;
+                    }
+                }
+
+                from_block = Some(latest_block + 1);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    async fn fetch_twice(cache: &AddressCache, key: &CachedAddress) -> usize {
+        let fetches = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            cache
+                .get_or_fetch(key.clone(), || async {
+                    fetches.fetch_add(1, Ordering::SeqCst);
+                    Ok(Address::repeat_byte(1))
+                })
+                .await
+                .unwrap();
+        }
+
+        fetches.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn router_addresses_never_expire_by_default() {
+        let cache = AddressCache::new(&AddressCacheConfig::default());
+
+        assert_eq!(fetch_twice(&cache, &CachedAddress::IbcStore).await, 1);
+        assert_eq!(fetch_twice(&cache, &CachedAddress::Ics02Client).await, 1);
+    }
+
+    #[tokio::test]
+    async fn light_client_addresses_always_expire() {
+        let key = CachedAddress::LightClient("07-tendermint-0".to_string());
+
+        let cache = AddressCache::new(&AddressCacheConfig::default());
+        assert_eq!(cache.client_ttl, Duration::from_secs(60));
+        assert_eq!(fetch_twice(&cache, &key).await, 1);
+
+        let cache = AddressCache::new(&AddressCacheConfig {
+            client_ttl_secs: 0,
+            ..AddressCacheConfig::default()
+        });
+        assert_eq!(fetch_twice(&cache, &key).await, 2);
+        assert_eq!(fetch_twice(&cache, &CachedAddress::IbcStore).await, 1);
+    }
+
+    #[tokio::test]
+    async fn light_client_addresses_expire_with_the_cache_ttl() {
+        let cache = AddressCache::new(&AddressCacheConfig {
+            ttl_secs: Some(0),
+            ..AddressCacheConfig::default()
+        });
+
+        assert_eq!(
+            fetch_twice(&cache, &CachedAddress::LightClient("client-0".to_string())).await,
+            2
+        );
+    }
+}
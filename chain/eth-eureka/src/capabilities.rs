@@ -0,0 +1,148 @@
+//! Capabilities of the execution RPC node, probed at startup
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    primitives::Address,
+    providers::{Provider, RootProvider},
+    transports::BoxTransport,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::error::ChainModuleError;
+
+/// How long the latest execution height is reused for, i.e. about one block
+const LATEST_HEIGHT_TTL: Duration = Duration::from_secs(12);
+
+/// How much historical state the execution RPC node serves
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct NodeCapabilities {
+    /// The number of blocks behind the latest block that `eth_getProof` can be served for.
+    /// This is `None` if the node is archival, or if the node was not probed.
+    pub state_window: Option<u64>,
+}
+
+impl NodeCapabilities {
+    /// Whether the node serves state at every height.
+    #[must_use]
+    pub const fn is_archival(&self) -> bool {
+        self.state_window.is_none()
+    }
+
+    /// The oldest execution height the node serves state for, given the latest execution
+    /// height, if the node is not archival.
+    #[must_use]
+    pub const fn oldest_available_height(&self, latest_height: u64) -> Option<u64> {
+        match self.state_window {
+            Some(state_window) => Some(latest_height.saturating_sub(state_window)),
+            None => None,
+        }
+    }
+}
+
+/// The latest execution height, fetched at most once per block so that checking the state
+/// window does not add a round trip to every query
+#[derive(Debug, Clone, Default)]
+pub struct LatestHeightCache(Arc<Mutex<Option<(u64, Instant)>>>);
+
+impl LatestHeightCache {
+    /// The latest execution height, fetched from `provider` if the cached one is stale.
+    /// # Errors
+    /// Returns an error if the rpc call fails.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub async fn get(
+        &self,
+        provider: &RootProvider<BoxTransport>,
+    ) -> Result<u64, ChainModuleError> {
+        let cached = *self.0.lock().expect("lock is poisoned");
+        if let Some((height, fetched_at)) = cached {
+            if fetched_at.elapsed() < LATEST_HEIGHT_TTL {
+                return Ok(height);
+            }
+        }
+
+        let height = provider.get_block_number().await?;
+
+        let mut cached = self.0.lock().expect("lock is poisoned");
+        // concurrent fetches may complete out of order
+        let height = cached.map_or(height, |(cached_height, _)| cached_height.max(height));
+        *cached = Some((height, Instant::now()));
+
+        Ok(height)
+    }
+}
+
+/// Probe how far back the node serves `eth_getProof` for `contract`.
+///
+/// The node is considered archival if it serves state at block 1. Otherwise, the depth of the
+/// state it serves is found by binary search.
+/// # Errors
+/// Returns an error if the node cannot be reached, if it does not serve state at the latest
+/// height, or if it fails for any other reason than missing state.
+pub async fn probe(
+    provider: &RootProvider<BoxTransport>,
+    contract: Address,
+) -> Result<NodeCapabilities, ChainModuleError> {
+    let latest_height = provider.get_block_number().await?;
+
+    // a node that does not serve the latest state cannot serve any query
+    fetch_proof(provider, contract, latest_height).await?;
+
+    if latest_height <= 1 || proof_available(provider, contract, 1).await? {
+        info!("the execution rpc node serves state at all heights");
+        return Ok(NodeCapabilities::default());
+    }
+
+    // state is available `low` blocks behind the latest block, but not `high` blocks behind it
+    let (mut low, mut high) = (0, latest_height - 1);
+    while high - low > 1 {
+        let depth = low + (high - low) / 2;
+
+        if proof_available(provider, contract, latest_height - depth).await? {
+            low = depth;
+        } else {
+            high = depth;
+        }
+    }
+
+    warn!(
+        state_window = low,
+        "the execution rpc node is not archival, state older than the state window is not \
+        available"
+    );
+
+    Ok(NodeCapabilities {
+        state_window: Some(low),
+    })
+}
+
+async fn proof_available(
+    provider: &RootProvider<BoxTransport>,
+    contract: Address,
+    execution_height: u64,
+) -> Result<bool, ChainModuleError> {
+    match fetch_proof(provider, contract, execution_height).await {
+        Ok(()) => Ok(true),
+        Err(ChainModuleError::PrunedState { .. }) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+async fn fetch_proof(
+    provider: &RootProvider<BoxTransport>,
+    contract: Address,
+    execution_height: u64,
+) -> Result<(), ChainModuleError> {
+    provider
+        .get_proof(contract, vec![])
+        .block_id(execution_height.into())
+        .await
+        .map(drop)
+        .map_err(|err| ChainModuleError::from_rpc(err, Some(execution_height)))
+}
@@ -0,0 +1,114 @@
+//! Light client registry for the Ethereum Eureka chain module
+
+use std::collections::BTreeMap;
+
+use ibc_eureka_types::{MOCK_CLIENT_TYPE, SOL_IBC_EUREKA_INTERFACE, SP1_ICS07_CLIENT_TYPE};
+use serde::{Deserialize, Serialize};
+use unionlabs::id::ClientId;
+
+use crate::error::ChainModuleError;
+
+/// The light client implementations that can be deployed behind `ICS02Client`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LightClientType {
+    /// The `sp1-ics07-tendermint` light client
+    Sp1Ics07Tendermint,
+    /// A mock or attestor light client that does not expose any client or consensus state
+    Mock,
+}
+
+impl LightClientType {
+    /// The voyager client type of this light client
+    #[must_use]
+    pub const fn client_type(self) -> &'static str {
+        match self {
+            Self::Sp1Ics07Tendermint => SP1_ICS07_CLIENT_TYPE,
+            Self::Mock => MOCK_CLIENT_TYPE,
+        }
+    }
+
+    /// The voyager IBC interface of this light client
+    #[must_use]
+    pub const fn ibc_interface(self) -> &'static str {
+        match self {
+            Self::Sp1Ics07Tendermint | Self::Mock => SOL_IBC_EUREKA_INTERFACE,
+        }
+    }
+}
+
+/// The configuration for the [`ClientRegistry`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ClientRegistryConfig {
+    /// The light client type behind each client ID prefix, e.g. `07-tendermint`.
+    /// Client IDs in IBC Eureka are of the form `{prefix}-{sequence}`.
+    pub prefixes: BTreeMap<String, LightClientType>,
+    /// The light client type of individual client IDs, taking precedence over `prefixes`.
+    pub clients: BTreeMap<String, LightClientType>,
+    /// The client prefix reported for raw client IDs.
+    pub default_prefix: String,
+}
+
+impl Default for ClientRegistryConfig {
+    fn default() -> Self {
+        Self {
+            prefixes: BTreeMap::from([(
+                "07-tendermint".to_string(),
+                LightClientType::Sp1Ics07Tendermint,
+            )]),
+            clients: BTreeMap::new(),
+            default_prefix: "07-tendermint".to_string(),
+        }
+    }
+}
+
+/// Resolves the light client type behind each client ID
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct ClientRegistry {
+    config: ClientRegistryConfig,
+}
+
+impl ClientRegistry {
+    /// Create a new registry from its configuration.
+    /// # Errors
+    /// Returns an error if the default prefix is not registered.
+    pub fn new(config: ClientRegistryConfig) -> Result<Self, String> {
+        if !config.prefixes.contains_key(&config.default_prefix) {
+            return Err(format!(
+                "default client prefix `{}` has no registered light client type",
+                config.default_prefix
+            ));
+        }
+
+        Ok(Self { config })
+    }
+
+    /// The client prefix reported for raw client IDs.
+    #[must_use]
+    pub fn default_prefix(&self) -> &str {
+        &self.config.default_prefix
+    }
+
+    /// Get the light client type behind a client ID.
+    /// # Errors
+    /// Returns an error if the client ID is neither registered explicitly nor by its prefix.
+    pub fn light_client_type(
+        &self,
+        client_id: &ClientId,
+    ) -> Result<LightClientType, ChainModuleError> {
+        let client_id = client_id.to_string();
+
+        if let Some(light_client_type) = self.config.clients.get(&client_id) {
+            return Ok(*light_client_type);
+        }
+
+        client_id
+            .rsplit_once('-')
+            .and_then(|(prefix, _)| self.config.prefixes.get(prefix))
+            .copied()
+            .ok_or(ChainModuleError::UnknownClientType { client_id })
+    }
+}
@@ -0,0 +1,301 @@
+//! Defines [`ChainModuleError`].
+
+use std::sync::OnceLock;
+
+use alloy::{
+    contract::Error as ContractError,
+    transports::{RpcError, TransportErrorKind},
+};
+use ibc_eureka_rpc::beacon::BeaconRequestError;
+use ibc_eureka_telemetry::metrics::metrics;
+use jsonrpsee::types::ErrorObjectOwned;
+use serde_json::json;
+use unionlabs::{ics24::Path, ErrorReporter};
+use voyager_message::FATAL_JSONRPC_ERROR_CODE;
+
+/// JSON-RPC error code for transient failures when talking to the execution RPC.
+pub const RPC_TRANSPORT_ERROR_CODE: i32 = -32_010;
+
+/// JSON-RPC error code for transient failures when talking to the beacon API.
+pub const BEACON_API_ERROR_CODE: i32 = -32_011;
+
+/// JSON-RPC error code for transient failures when talking to a counterparty Tendermint RPC.
+pub const TENDERMINT_RPC_ERROR_CODE: i32 = -32_012;
+
+/// JSON-RPC error code for proofs returned by the execution RPC that fail local verification.
+pub const INVALID_PROOF_ERROR_CODE: i32 = -32_013;
+
+/// JSON-RPC error code for latest height queries made before any height is finalized.
+pub const NO_FINALIZED_HEIGHT_ERROR_CODE: i32 = -32_014;
+
+/// The module that errors are counted under in the metrics, unless set with
+/// [`set_metrics_module`]
+const DEFAULT_METRICS_MODULE: &str = "eth-eureka";
+
+static METRICS_MODULE: OnceLock<&'static str> = OnceLock::new();
+
+/// Count the errors returned over JSON-RPC under `module` rather than `eth-eureka`, for chain
+/// modules serving their queries through this one. This must be called before any error is
+/// returned, and only the first call has an effect.
+pub fn set_metrics_module(module: &'static str) {
+    let _ = METRICS_MODULE.set(module);
+}
+
+/// The JSON-RPC error code used when the execution node reverts a call. Geth and most other
+/// clients use this code for `execution reverted` responses.
+const EXECUTION_REVERTED_CODE: i64 = 3;
+
+/// Error messages returned by execution clients when the requested historical state is no
+/// longer available.
+const PRUNED_STATE_MESSAGES: &[&str] = &[
+    "missing trie node",
+    "historical state",
+    "state is not available",
+    "state not available",
+];
+
+/// Errors that can occur when serving queries from the Ethereum Eureka chain module.
+#[derive(Debug, thiserror::Error)]
+#[allow(clippy::module_name_repetitions)]
+pub enum ChainModuleError {
+    /// The execution RPC could not be reached or returned an unexpected error.
+    #[error("execution rpc request failed")]
+    Rpc(#[source] RpcError<TransportErrorKind>),
+    /// A contract call was reverted by the execution client.
+    #[error("contract call reverted: {0}")]
+    ContractRevert(String),
+    /// The beacon API could not be reached or returned an error.
+    #[error("beacon api request failed")]
+    BeaconApi(#[source] beacon_api::errors::Error),
+    /// Not enough beacon API endpoints returned matching responses for a quorum read.
+    #[error("no quorum of {threshold} matching responses from the beacon api endpoints")]
+    NoBeaconQuorum {
+        /// The number of matching responses required.
+        threshold: usize,
+    },
+    /// The counterparty Tendermint RPC could not be reached or returned an error.
+    #[error("tendermint rpc request failed: {0}")]
+    TendermintRpc(String),
+    /// No Tendermint RPC is configured for the counterparty chain of a client.
+    #[error("no tendermint rpc configured for counterparty chain `{chain_id}`")]
+    MissingCounterpartyRpc {
+        /// The chain ID of the counterparty chain.
+        chain_id: String,
+    },
+    /// The light client type behind a client ID is not registered.
+    #[error("no light client type registered for client `{client_id}`")]
+    UnknownClientType {
+        /// The client ID.
+        client_id: String,
+    },
+    /// The client state of a client does not exist at the queried height.
+    #[error("client state of `{client_id}` not found at execution height {execution_height}")]
+    ClientStateNotFound {
+        /// The client ID.
+        client_id: String,
+        /// The execution height that was queried.
+        execution_height: u64,
+    },
+    /// A beacon API is required, but none is configured.
+    #[error("no beacon api configured")]
+    MissingBeaconApi,
+    /// The packet indexer database failed.
+    #[error("packet indexer database error")]
+    Indexer(#[source] redb::Error),
+    /// A query requires the packet indexer, but it is not enabled.
+    #[error("the packet indexer is not enabled")]
+    IndexerDisabled,
+    /// The requested path does not exist in IBC Eureka.
+    #[error("path `{0}` is not supported by ibc eureka")]
+    UnsupportedPath(Path),
+    /// The execution client has pruned the state at the requested height.
+    #[error("state at execution height {execution_height} has been pruned: {message}")]
+    PrunedState {
+        /// The execution height that was queried.
+        execution_height: u64,
+        /// The error message returned by the execution client.
+        message: String,
+    },
+    /// The execution RPC node does not serve state at the requested height. Point the module at
+    /// an archive node, or query a newer height.
+    #[error(
+        "state unavailable at execution height {execution_height}, the oldest height served by \
+        the execution rpc is {oldest_available_height}"
+    )]
+    StateUnavailable {
+        /// The execution height that was queried.
+        execution_height: u64,
+        /// The oldest execution height the execution RPC serves state for.
+        oldest_available_height: u64,
+    },
+    /// A proof returned by the execution RPC failed local verification.
+    #[error("invalid proof: {0}")]
+    InvalidProof(String),
+    /// An upstream response could not be interpreted.
+    #[error("malformed response: {0}")]
+    MalformedResponse(String),
+    /// No height of the chain is finalized yet, e.g. no L2 output has been proposed to L1.
+    #[error("no finalized height: {0}")]
+    NoFinalizedHeight(String),
+}
+
+impl ChainModuleError {
+    /// Classify an error returned by the execution RPC for a request made at
+    /// `execution_height`, if any.
+    #[must_use]
+    pub fn from_rpc(err: RpcError<TransportErrorKind>, execution_height: Option<u64>) -> Self {
+        match &err {
+            RpcError::ErrorResp(payload) if payload.code == EXECUTION_REVERTED_CODE => {
+                Self::ContractRevert(payload.message.to_string())
+            }
+            RpcError::ErrorResp(payload) => match execution_height {
+                Some(execution_height)
+                    if PRUNED_STATE_MESSAGES
+                        .iter()
+                        .any(|msg| payload.message.contains(msg)) =>
+                {
+                    Self::PrunedState {
+                        execution_height,
+                        message: payload.message.to_string(),
+                    }
+                }
+                _ => Self::Rpc(err),
+            },
+            RpcError::DeserError { .. } => Self::MalformedResponse(ErrorReporter(err).to_string()),
+            _ => Self::Rpc(err),
+        }
+    }
+
+    /// Classify an error returned by a contract call made at `execution_height`, if any.
+    #[must_use]
+    pub fn from_contract(err: ContractError, execution_height: Option<u64>) -> Self {
+        match err {
+            ContractError::TransportError(err) => Self::from_rpc(err, execution_height),
+            ContractError::AbiError(_) => Self::MalformedResponse(ErrorReporter(err).to_string()),
+            err => Self::ContractRevert(ErrorReporter(err).to_string()),
+        }
+    }
+
+    /// Whether the error is transient, i.e. the same request may succeed if retried.
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Rpc(_)
+                | Self::BeaconApi(_)
+                | Self::NoBeaconQuorum { .. }
+                | Self::TendermintRpc(_)
+                | Self::InvalidProof(_)
+                | Self::NoFinalizedHeight(_)
+        )
+    }
+
+    /// A short, stable identifier for the kind of this error.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Rpc(_) => "rpc",
+            Self::ContractRevert(_) => "contract_revert",
+            Self::BeaconApi(_) => "beacon_api",
+            Self::NoBeaconQuorum { .. } => "no_beacon_quorum",
+            Self::TendermintRpc(_) => "tendermint_rpc",
+            Self::MissingCounterpartyRpc { .. } => "missing_counterparty_rpc",
+            Self::UnknownClientType { .. } => "unknown_client_type",
+            Self::ClientStateNotFound { .. } => "client_state_not_found",
+            Self::MissingBeaconApi => "missing_beacon_api",
+            Self::Indexer(_) => "indexer",
+            Self::IndexerDisabled => "indexer_disabled",
+            Self::UnsupportedPath(_) => "unsupported_path",
+            Self::PrunedState { .. } => "pruned_state",
+            Self::StateUnavailable { .. } => "state_unavailable",
+            Self::InvalidProof(_) => "invalid_proof",
+            Self::MalformedResponse(_) => "malformed_response",
+            Self::NoFinalizedHeight(_) => "no_finalized_height",
+        }
+    }
+
+    /// The JSON-RPC error code for this error. Non-retryable errors use
+    /// [`FATAL_JSONRPC_ERROR_CODE`] so that voyager does not requeue them.
+    #[must_use]
+    pub const fn code(&self) -> i32 {
+        match self {
+            Self::Rpc(_) => RPC_TRANSPORT_ERROR_CODE,
+            Self::BeaconApi(_) | Self::NoBeaconQuorum { .. } => BEACON_API_ERROR_CODE,
+            Self::TendermintRpc(_) => TENDERMINT_RPC_ERROR_CODE,
+            Self::InvalidProof(_) => INVALID_PROOF_ERROR_CODE,
+            Self::NoFinalizedHeight(_) => NO_FINALIZED_HEIGHT_ERROR_CODE,
+            Self::ContractRevert(_)
+            | Self::MissingCounterpartyRpc { .. }
+            | Self::UnknownClientType { .. }
+            | Self::ClientStateNotFound { .. }
+            | Self::MissingBeaconApi
+            | Self::Indexer(_)
+            | Self::IndexerDisabled
+            | Self::UnsupportedPath(_)
+            | Self::PrunedState { .. }
+            | Self::StateUnavailable { .. }
+            | Self::MalformedResponse(_) => FATAL_JSONRPC_ERROR_CODE,
+        }
+    }
+}
+
+impl From<RpcError<TransportErrorKind>> for ChainModuleError {
+    fn from(err: RpcError<TransportErrorKind>) -> Self {
+        Self::from_rpc(err, None)
+    }
+}
+
+impl From<ContractError> for ChainModuleError {
+    fn from(err: ContractError) -> Self {
+        Self::from_contract(err, None)
+    }
+}
+
+impl From<beacon_api::errors::Error> for ChainModuleError {
+    fn from(err: beacon_api::errors::Error) -> Self {
+        Self::BeaconApi(err)
+    }
+}
+
+impl From<BeaconRequestError> for ChainModuleError {
+    fn from(err: BeaconRequestError) -> Self {
+        match err {
+            BeaconRequestError::Request(err) => Self::BeaconApi(err),
+            BeaconRequestError::NoQuorum { threshold } => Self::NoBeaconQuorum { threshold },
+        }
+    }
+}
+
+impl From<ChainModuleError> for ErrorObjectOwned {
+    fn from(err: ChainModuleError) -> Self {
+        metrics().record_error(
+            METRICS_MODULE.get_or_init(|| DEFAULT_METRICS_MODULE),
+            err.kind(),
+        );
+
+        let data = match &err {
+            ChainModuleError::PrunedState {
+                execution_height, ..
+            } => json!({
+                "kind": err.kind(),
+                "retryable": err.is_retryable(),
+                "execution_height": execution_height,
+            }),
+            ChainModuleError::StateUnavailable {
+                execution_height,
+                oldest_available_height,
+            } => json!({
+                "kind": err.kind(),
+                "retryable": err.is_retryable(),
+                "execution_height": execution_height,
+                "oldest_available_height": oldest_available_height,
+            }),
+            _ => json!({
+                "kind": err.kind(),
+                "retryable": err.is_retryable(),
+            }),
+        };
+
+        Self::owned(err.code(), ErrorReporter(&err).to_string(), Some(data))
+    }
+}
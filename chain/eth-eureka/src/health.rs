@@ -0,0 +1,65 @@
+//! Health checks of the upstream dependencies of the module
+
+use alloy::providers::Provider;
+use beacon_api::client::BlockId;
+use ibc_eureka_rpc::{beacon::BEACON_API_DEPENDENCY, execution::EXECUTION_RPC_DEPENDENCY};
+use ibc_eureka_telemetry::health::health;
+use tendermint_rpc::Client;
+
+use crate::Module;
+
+/// The name of the Tendermint RPC of the counterparty chain `chain_id` in the health report.
+#[must_use]
+pub fn tendermint_rpc_dependency(chain_id: &str) -> String {
+    format!("tendermint_rpc/{chain_id}")
+}
+
+/// Register the readiness checks of the execution RPC, the beacon API if configured, and the
+/// Tendermint RPCs of all counterparty chains. Each check reports the latest height of the
+/// dependency.
+pub fn register_checks(module: &Module) {
+    let eth_provider = module.eth_provider.clone();
+    health().register_check(EXECUTION_RPC_DEPENDENCY, move || {
+        let eth_provider = eth_provider.clone();
+        async move {
+            eth_provider
+                .get_block_number()
+                .await
+                .map(Some)
+                .map_err(|err| err.to_string())
+        }
+    });
+
+    if let Some(beacon_api_client) = &module.beacon_api_client {
+        let beacon_api_client = beacon_api_client.clone();
+        health().register_check(BEACON_API_DEPENDENCY, move || {
+            let beacon_api_client = beacon_api_client.clone();
+            async move {
+                beacon_api_client
+                    .request(|client| async move {
+                        client
+                            .block(BlockId::Head)
+                            .await
+                            .map(|block| block.data.message.slot)
+                    })
+                    .await
+                    .map(Some)
+                    .map_err(|err| err.to_string())
+            }
+        });
+    }
+
+    for (chain_id, tm_client) in &module.counterparty_tm_clients {
+        let tm_client = tm_client.clone();
+        health().register_check(tendermint_rpc_dependency(chain_id), move || {
+            let tm_client = tm_client.clone();
+            async move {
+                tm_client
+                    .status()
+                    .await
+                    .map(|status| Some(status.sync_info.latest_block_height.value()))
+                    .map_err(|err| err.to_string())
+            }
+        });
+    }
+}
@@ -0,0 +1,96 @@
+//! Height semantics of the Ethereum Eureka chain module
+
+use alloy::eips::BlockNumberOrTag;
+use serde::{Deserialize, Serialize};
+
+use crate::error::ChainModuleError;
+
+/// The number of nanoseconds in a second
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+
+/// How the heights used by voyager map onto the Ethereum chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeightMode {
+    /// Heights are beacon slots, and finality is tracked through the beacon API
+    #[default]
+    BeaconSlot,
+    /// Heights are execution block numbers, and finality is tracked through the execution
+    /// block tags. Use this for chains without a beacon API, such as Anvil, L2s or PoA chains.
+    ExecutionBlock,
+}
+
+/// The header or block tag that is considered final by `query_latest_height` and
+/// `query_latest_timestamp`, trading latency against reorg safety
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Finality {
+    /// The finalized header of the latest beacon light client finality update
+    FinalizedHeader,
+    /// The attested header of the latest beacon light client finality update
+    AttestedHeader,
+    /// The `finalized` execution block tag
+    Finalized,
+    /// The `safe` execution block tag
+    Safe,
+    /// The `latest` execution block tag
+    Latest,
+}
+
+impl Finality {
+    /// The default finality for a [`HeightMode`].
+    #[must_use]
+    pub const fn default_for(height_mode: HeightMode) -> Self {
+        match height_mode {
+            HeightMode::BeaconSlot => Self::AttestedHeader,
+            HeightMode::ExecutionBlock => Self::Finalized,
+        }
+    }
+
+    /// Whether this finality can be used with a [`HeightMode`]. Beacon headers can only be used
+    /// with beacon slots, and execution block tags can only be used with execution heights.
+    #[must_use]
+    pub const fn supports(self, height_mode: HeightMode) -> bool {
+        matches!(
+            (self, height_mode),
+            (
+                Self::FinalizedHeader | Self::AttestedHeader,
+                HeightMode::BeaconSlot
+            ) | (
+                Self::Finalized | Self::Safe | Self::Latest,
+                HeightMode::ExecutionBlock
+            )
+        )
+    }
+
+    /// The execution block tag of this finality, if it is one.
+    #[must_use]
+    pub const fn block_tag(self) -> Option<BlockNumberOrTag> {
+        match self {
+            Self::FinalizedHeader | Self::AttestedHeader => None,
+            Self::Finalized => Some(BlockNumberOrTag::Finalized),
+            Self::Safe => Some(BlockNumberOrTag::Safe),
+            Self::Latest => Some(BlockNumberOrTag::Latest),
+        }
+    }
+}
+
+/// A consistent view of the latest height and its timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatestSnapshot {
+    /// The latest height, according to the [`HeightMode`]
+    pub height: u64,
+    /// The execution timestamp at `height`, in seconds
+    pub timestamp: u64,
+}
+
+/// Convert an execution timestamp in seconds to nanoseconds.
+/// # Errors
+/// Returns an error if the timestamp overflows a `u64` in nanoseconds.
+pub fn timestamp_nanos(timestamp_secs: u64) -> Result<u64, ChainModuleError> {
+    timestamp_secs.checked_mul(NANOS_PER_SECOND).ok_or_else(|| {
+        ChainModuleError::MalformedResponse(format!(
+            "execution timestamp {timestamp_secs} overflows when converted to nanoseconds"
+        ))
+    })
+}
@@ -0,0 +1,426 @@
+//! Packet event indexer for the `ICS26Router`
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::Address,
+    providers::{Provider, RootProvider},
+    rpc::types::{BlockTransactionsKind, Filter, Log},
+    sol_types::SolEventInterface,
+    transports::BoxTransport,
+};
+use ibc_eureka_solidity::ics26::router::routerEvents;
+use redb::{
+    Database, MultimapTableDefinition, ReadableMultimapTable, ReadableTable, ReadableTableMetadata,
+    TableDefinition,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::error::ChainModuleError;
+
+/// Packets sent, keyed by source client and sequence
+const SENT: TableDefinition<(&str, u64), u64> = TableDefinition::new("sent");
+/// The source client and sequence of the packets sent, keyed by block
+const SENT_BY_BLOCK: MultimapTableDefinition<u64, (&str, u64)> =
+    MultimapTableDefinition::new("sent_by_block");
+/// Packets received, keyed by destination client and sequence
+const RECEIVED: TableDefinition<(&str, u64), u64> = TableDefinition::new("received");
+/// Acknowledgements written, keyed by destination client and sequence
+const ACK_WRITTEN: TableDefinition<(&str, u64), u64> = TableDefinition::new("ack_written");
+/// Packets acknowledged, keyed by source client and sequence
+const ACKNOWLEDGED: TableDefinition<(&str, u64), u64> = TableDefinition::new("acknowledged");
+/// Packets timed out, keyed by source client and sequence
+const TIMED_OUT: TableDefinition<(&str, u64), u64> = TableDefinition::new("timed_out");
+/// Indexer metadata
+const META: TableDefinition<&str, u64> = TableDefinition::new("meta");
+
+/// The key of the last indexed block in [`META`]
+const LAST_INDEXED_BLOCK: &str = "last_indexed_block";
+
+/// The configuration for the [`PacketIndexer`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(clippy::module_name_repetitions)]
+pub struct IndexerConfig {
+    /// The path of the indexer database.
+    pub db_path: PathBuf,
+    /// The execution block to start indexing from, if the database is empty.
+    #[serde(default)]
+    pub start_block: u64,
+    /// The maximum number of blocks to fetch logs for in a single `eth_getLogs` call.
+    #[serde(default = "default_max_block_range")]
+    pub max_block_range: u64,
+    /// How often to poll for new finalized blocks, in seconds.
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+}
+
+const fn default_max_block_range() -> u64 {
+    1_000
+}
+
+const fn default_poll_interval_secs() -> u64 {
+    12
+}
+
+/// The kind of an indexed packet event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketEventKind {
+    /// `SendPacket`
+    Send,
+    /// `RecvPacket`
+    Recv,
+    /// `WriteAcknowledgement`
+    WriteAcknowledgement,
+    /// `AckPacket`
+    Acknowledgement,
+    /// `TimeoutPacket`
+    Timeout,
+}
+
+impl PacketEventKind {
+    const fn table(self) -> TableDefinition<'static, (&'static str, u64), u64> {
+        match self {
+            Self::Send => SENT,
+            Self::Recv => RECEIVED,
+            Self::WriteAcknowledgement => ACK_WRITTEN,
+            Self::Acknowledgement => ACKNOWLEDGED,
+            Self::Timeout => TIMED_OUT,
+        }
+    }
+}
+
+/// A packet event found by the indexer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedPacket {
+    /// The client the packet is keyed by: the source client for sends, acknowledgements and
+    /// timeouts, and the destination client for receives and written acknowledgements
+    pub client_id: String,
+    /// The packet sequence
+    pub sequence: u64,
+    /// The execution block the event was emitted in
+    pub block_number: u64,
+}
+
+/// Indexes the packet lifecycle events emitted by the `ICS26Router` into a local database
+#[derive(Debug, Clone)]
+#[allow(clippy::module_name_repetitions)]
+pub struct PacketIndexer {
+    db: Arc<Database>,
+    router: Address,
+    provider: RootProvider<BoxTransport>,
+    start_block: u64,
+    max_block_range: u64,
+}
+
+impl PacketIndexer {
+    /// Open (or create) the indexer database.
+    /// # Errors
+    /// Returns an error if the database cannot be opened.
+    pub fn new(
+        config: &IndexerConfig,
+        router: Address,
+        provider: RootProvider<BoxTransport>,
+    ) -> Result<Self, ChainModuleError> {
+        let db = Database::create(&config.db_path).map_err(db_err)?;
+
+        // create all tables, so that read transactions never see a missing table
+        let tx = db.begin_write().map_err(db_err)?;
+        for table in [SENT, RECEIVED, ACK_WRITTEN, ACKNOWLEDGED, TIMED_OUT] {
+            tx.open_table(table).map_err(db_err)?;
+        }
+        tx.open_table(META).map_err(db_err)?;
+        backfill_sent_by_block(&tx)?;
+        tx.commit().map_err(db_err)?;
+
+        Ok(Self {
+            db: Arc::new(db),
+            router,
+            provider,
+            start_block: config.start_block,
+            max_block_range: config.max_block_range.max(1),
+        })
+    }
+
+    /// The last execution block that has been indexed, if any.
+    /// # Errors
+    /// Returns an error if the database read fails.
+    pub fn last_indexed_block(&self) -> Result<Option<u64>, ChainModuleError> {
+        let tx = self.db.begin_read().map_err(db_err)?;
+        let meta = tx.open_table(META).map_err(db_err)?;
+
+        Ok(meta
+            .get(LAST_INDEXED_BLOCK)
+            .map_err(db_err)?
+            .map(|block| block.value()))
+    }
+
+    /// Spawn a task that indexes new finalized blocks every `poll_interval`.
+    pub fn spawn(&self, poll_interval: Duration) {
+        let indexer = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+
+            loop {
+                interval.tick().await;
+
+                if let Err(err) = indexer.index_to_finalized().await {
+                    warn!(%err, "unable to index packet events");
+                }
+            }
+        });
+    }
+
+    /// Index all blocks between the last indexed block and the latest finalized block.
+    /// # Errors
+    /// Returns an error if the rpc calls or database writes fail.
+    pub async fn index_to_finalized(&self) -> Result<(), ChainModuleError> {
+        let finalized_block = self
+            .provider
+            .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+            .await?
+            .ok_or_else(|| {
+                ChainModuleError::MalformedResponse("finalized block not found".to_string())
+            })?
+            .header
+            .number;
+
+        let from_block = self
+            .last_indexed_block()?
+            .map_or(self.start_block, |block| block + 1);
+
+        if from_block > finalized_block {
+            return Ok(());
+        }
+
+        self.index_range(from_block, finalized_block).await
+    }
+
+    /// Index the packet events emitted between `from_block` and `to_block` (inclusive).
+    /// # Errors
+    /// Returns an error if the rpc calls or database writes fail.
+    pub async fn index_range(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<(), ChainModuleError> {
+        let mut chunk_start = from_block;
+
+        while chunk_start <= to_block {
+            let chunk_end = to_block.min(chunk_start + self.max_block_range - 1);
+
+            let filter = Filter::new()
+                .address(self.router)
+                .from_block(chunk_start)
+                .to_block(chunk_end);
+            let logs = self.provider.get_logs(&filter).await?;
+
+            let events = logs
+                .iter()
+                .filter_map(decode_packet_event)
+                .collect::<Vec<_>>();
+            self.store(&events, chunk_end)?;
+
+            debug!(
+                from_block = chunk_start,
+                to_block = chunk_end,
+                events = events.len(),
+                "indexed packet events"
+            );
+
+            chunk_start = chunk_end + 1;
+        }
+
+        info!(to_block, "indexed packet events up to block");
+
+        Ok(())
+    }
+
+    fn store(
+        &self,
+        events: &[(PacketEventKind, IndexedPacket)],
+        last_block: u64,
+    ) -> Result<(), ChainModuleError> {
+        let tx = self.db.begin_write().map_err(db_err)?;
+
+        for (kind, packet) in events {
+            tx.open_table(kind.table())
+                .map_err(db_err)?
+                .insert(
+                    (packet.client_id.as_str(), packet.sequence),
+                    packet.block_number,
+                )
+                .map_err(db_err)?;
+
+            if *kind == PacketEventKind::Send {
+                tx.open_multimap_table(SENT_BY_BLOCK)
+                    .map_err(db_err)?
+                    .insert(
+                        packet.block_number,
+                        (packet.client_id.as_str(), packet.sequence),
+                    )
+                    .map_err(db_err)?;
+            }
+        }
+
+        tx.open_table(META)
+            .map_err(db_err)?
+            .insert(LAST_INDEXED_BLOCK, last_block)
+            .map_err(db_err)?;
+
+        tx.commit().map_err(db_err)
+    }
+
+    /// All indexed events of `kind` for `client_id`.
+    /// # Errors
+    /// Returns an error if the database read fails.
+    pub fn packets(
+        &self,
+        kind: PacketEventKind,
+        client_id: &str,
+    ) -> Result<Vec<IndexedPacket>, ChainModuleError> {
+        let tx = self.db.begin_read().map_err(db_err)?;
+        let table = tx.open_table(kind.table()).map_err(db_err)?;
+
+        table
+            .range((client_id, 0)..=(client_id, u64::MAX))
+            .map_err(db_err)?
+            .map(|entry| {
+                let (key, block_number) = entry.map_err(db_err)?;
+
+                Ok(IndexedPacket {
+                    client_id: client_id.to_string(),
+                    sequence: key.value().1,
+                    block_number: block_number.value(),
+                })
+            })
+            .collect()
+    }
+
+    /// The sequences of the packets sent from `client_id` whose commitments have not been
+    /// cleared by an acknowledgement or a timeout yet.
+    /// # Errors
+    /// Returns an error if the database read fails.
+    pub fn pending_commitments(&self, client_id: &str) -> Result<Vec<u64>, ChainModuleError> {
+        let tx = self.db.begin_read().map_err(db_err)?;
+        let acknowledged = tx.open_table(ACKNOWLEDGED).map_err(db_err)?;
+        let timed_out = tx.open_table(TIMED_OUT).map_err(db_err)?;
+
+        let mut pending = vec![];
+        for packet in self.packets(PacketEventKind::Send, client_id)? {
+            let key = (client_id, packet.sequence);
+            if acknowledged.get(key).map_err(db_err)?.is_none()
+                && timed_out.get(key).map_err(db_err)?.is_none()
+            {
+                pending.push(packet.sequence);
+            }
+        }
+
+        Ok(pending)
+    }
+
+    /// All packets sent between `from_block` and `to_block` (inclusive), from any client.
+    /// # Errors
+    /// Returns an error if the database read fails.
+    pub fn sent_between(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<IndexedPacket>, ChainModuleError> {
+        if from_block > to_block {
+            return Ok(vec![]);
+        }
+
+        let tx = self.db.begin_read().map_err(db_err)?;
+        let sent_by_block = tx.open_multimap_table(SENT_BY_BLOCK).map_err(db_err)?;
+
+        let mut packets = vec![];
+        for entry in sent_by_block.range(from_block..=to_block).map_err(db_err)? {
+            let (block_number, sent) = entry.map_err(db_err)?;
+
+            for packet in sent {
+                let (client_id, sequence) = packet.map_err(db_err)?.value();
+
+                packets.push(IndexedPacket {
+                    client_id: client_id.to_string(),
+                    sequence,
+                    block_number: block_number.value(),
+                });
+            }
+        }
+
+        Ok(packets)
+    }
+}
+
+/// Index the packets in [`SENT`] by block, if [`SENT_BY_BLOCK`] is empty. Databases created
+/// before [`SENT_BY_BLOCK`] was added are indexed once when they are opened.
+fn backfill_sent_by_block(tx: &redb::WriteTransaction) -> Result<(), ChainModuleError> {
+    let sent = tx.open_table(SENT).map_err(db_err)?;
+    let mut sent_by_block = tx.open_multimap_table(SENT_BY_BLOCK).map_err(db_err)?;
+
+    if !sent_by_block.is_empty().map_err(db_err)? {
+        return Ok(());
+    }
+
+    for entry in sent.iter().map_err(db_err)? {
+        let (key, block_number) = entry.map_err(db_err)?;
+        sent_by_block
+            .insert(block_number.value(), key.value())
+            .map_err(db_err)?;
+    }
+
+    Ok(())
+}
+
+/// Decode a router log into a packet event, ignoring logs that are not packet events.
+fn decode_packet_event(log: &Log) -> Option<(PacketEventKind, IndexedPacket)> {
+    let block_number = log.block_number?;
+    let event = routerEvents::decode_log(&log.inner, true).ok()?.data;
+
+    let (kind, client_id, sequence) = match event {
+        routerEvents::SendPacket(event) => (
+            PacketEventKind::Send,
+            event.packet.sourceChannel,
+            event.packet.sequence,
+        ),
+        routerEvents::RecvPacket(event) => (
+            PacketEventKind::Recv,
+            event.packet.destChannel,
+            event.packet.sequence,
+        ),
+        routerEvents::WriteAcknowledgement(event) => (
+            PacketEventKind::WriteAcknowledgement,
+            event.packet.destChannel,
+            event.packet.sequence,
+        ),
+        routerEvents::AckPacket(event) => (
+            PacketEventKind::Acknowledgement,
+            event.packet.sourceChannel,
+            event.packet.sequence,
+        ),
+        routerEvents::TimeoutPacket(event) => (
+            PacketEventKind::Timeout,
+            event.packet.sourceChannel,
+            event.packet.sequence,
+        ),
+        _ => return None,
+    };
+
+    Some((
+        kind,
+        IndexedPacket {
+            client_id,
+            sequence: sequence.into(),
+            block_number,
+        },
+    ))
+}
+
+fn db_err(err: impl Into<redb::Error>) -> ChainModuleError {
+    ChainModuleError::Indexer(err.into())
+}
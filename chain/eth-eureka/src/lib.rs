@@ -0,0 +1,1423 @@
+//! Ethereum Chain Module for IBC Eureka
+
+#![deny(clippy::nursery, clippy::pedantic, warnings, missing_docs)]
+
+use std::{
+    collections::BTreeMap,
+    num::NonZeroU64,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    eips::{BlockId as EthBlockId, BlockNumberOrTag},
+    primitives::{keccak256, Address, B256},
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::types::{Block, BlockTransactionsKind},
+    sol_types::{SolCall, SolValue},
+    transports::BoxTransport,
+};
+use beacon_api::client::{BeaconApiClient, BlockId};
+use cache::{AddressCache, AddressCacheConfig, CachedAddress};
+use capabilities::{LatestHeightCache, NodeCapabilities};
+use client::{ClientRegistry, ClientRegistryConfig, LightClientType};
+use error::ChainModuleError;
+use futures::future::try_join_all;
+use height::{timestamp_nanos, Finality, HeightMode, LatestSnapshot};
+use ibc_eureka_rpc::{
+    beacon::FailoverBeaconClient,
+    config::{Endpoints, FailoverConfig, QuorumConfig},
+    execution::FailoverTransport,
+    retry::{RateLimitConfig, RetryConfig},
+};
+use ibc_eureka_solidity::{
+    ibc_store::{store as ibc_store, store::storeInstance},
+    ics02::client as ics02_client,
+    ics26::router::{self as ics26_router, routerInstance},
+};
+use ibc_eureka_telemetry::{metrics::metrics, server::TelemetryConfig};
+use ibc_eureka_union_ext::path::IbcEurekaPathExt;
+use indexer::{IndexedPacket, IndexerConfig, PacketIndexer};
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    Extensions,
+};
+use multicall::{Multicall, MulticallConfig};
+use proof::{
+    commitment_location, BatchedStorageProof, ExtendedStorageProof, ProofFormat, StorageMultiProof,
+};
+use query::QueryServerConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sp1_ics07_tendermint_solidity::{sp1_ics07_tendermint, IICS07TendermintMsgs::ConsensusState};
+use sp1_ics07_tendermint_utils::{light_block::LightBlockExt, rpc::TendermintRpcExt};
+use tendermint_rpc::{HttpClient, Url};
+use unionlabs::{
+    bytes::Bytes,
+    hash::H256,
+    ibc::core::{
+        channel::channel::Channel, client::height::Height,
+        connection::connection_end::ConnectionEnd,
+    },
+    ics24::{
+        AcknowledgementPath, ChannelEndPath, ClientConsensusStatePath, ClientStatePath,
+        CommitmentPath, ConnectionPath, NextClientSequencePath, NextConnectionSequencePath,
+        NextSequenceAckPath, NextSequenceRecvPath, NextSequenceSendPath, Path, ReceiptPath,
+    },
+    id::{ChannelId, ClientId, ConnectionId, PortId},
+    uint::U256,
+};
+use voyager_message::{
+    core::{ChainId, ClientInfo, ClientType, IbcInterface},
+    module::{ChainModuleInfo, ChainModuleServer, RawClientState},
+    ChainModule,
+};
+use voyager_vm::BoxDynError;
+
+pub mod cache;
+pub mod capabilities;
+pub mod client;
+pub mod error;
+pub mod health;
+pub mod height;
+pub mod indexer;
+pub mod multicall;
+pub mod proof;
+pub mod query;
+pub mod verify;
+
+#[cfg(test)]
+mod tests;
+
+/// The Ethereum Eureka Chain Module
+#[derive(Debug, Clone)]
+pub struct Module {
+    /// The chain ID of the Ethereum chain
+    pub chain_id: ChainId<'static>,
+
+    /// The ics26 router contract instance
+    pub ics26_router: routerInstance<BoxTransport, RootProvider<BoxTransport>>,
+
+    /// The ethereum provider
+    pub eth_provider: RootProvider<BoxTransport>,
+    /// The RPC endpoints for the beacon api, if any.
+    pub beacon_api_client: Option<FailoverBeaconClient>,
+
+    /// How heights map onto the Ethereum chain
+    pub height_mode: HeightMode,
+    /// The header or block tag considered final by the latest height queries
+    pub finality: Finality,
+
+    /// Tendermint RPC clients for the counterparty chains, keyed by chain ID
+    pub counterparty_tm_clients: BTreeMap<String, HttpClient>,
+
+    /// The light client types behind each client ID
+    pub client_registry: ClientRegistry,
+
+    /// The contract addresses derived from the ics26 router
+    pub address_cache: AddressCache,
+
+    /// The format of the proofs returned by `query_ibc_proof`
+    pub proof_format: ProofFormat,
+    /// Whether to verify proofs against the execution state root before returning them
+    pub verify_proofs: bool,
+
+    /// The packet event indexer, if enabled
+    pub packet_indexer: Option<PacketIndexer>,
+
+    /// The `Multicall3` batcher for contract reads, if enabled
+    pub multicall: Option<Multicall>,
+
+    /// How much historical state the execution RPC node serves
+    pub node_capabilities: NodeCapabilities,
+    /// The latest execution height, used to check the state window of non-archival nodes
+    pub latest_execution_height: LatestHeightCache,
+}
+
+/// The configuration for the Ethereum Eureka Chain Module
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// The address of the `IBCHandler` smart contract.
+    pub ics26_router_address: String,
+
+    /// The RPC endpoint(s) for the execution chain, in order of preference.
+    pub eth_rpc_api: Endpoints,
+    /// The RPC endpoint(s) for the beacon chain, in order of preference. Required if
+    /// `height_mode` is `beacon_slot`.
+    #[serde(default)]
+    pub eth_beacon_rpc_api: Option<Endpoints>,
+    /// When an RPC endpoint is considered unhealthy and failed over from.
+    #[serde(default)]
+    pub rpc_failover: FailoverConfig,
+    /// Require matching responses from multiple endpoints for critical reads. The threshold
+    /// applies to both the execution and the beacon endpoints.
+    #[serde(default)]
+    pub rpc_quorum: Option<QuorumConfig>,
+    /// How failed execution and beacon RPC requests are retried.
+    #[serde(default)]
+    pub rpc_retry: RetryConfig,
+    /// The request rate limit of each execution and beacon RPC endpoint. Requests are not rate
+    /// limited if this is not set.
+    #[serde(default)]
+    pub rpc_rate_limit: Option<RateLimitConfig>,
+
+    /// How heights map onto the Ethereum chain.
+    #[serde(default)]
+    pub height_mode: HeightMode,
+    /// The header or block tag considered final by the latest height queries. Defaults to
+    /// `attested_header` if `height_mode` is `beacon_slot`, and to `finalized` otherwise.
+    #[serde(default)]
+    pub finality: Option<Finality>,
+
+    /// The Tendermint RPC endpoints of the counterparty chains, keyed by chain ID.
+    /// The SP1 ICS07 contract only stores consensus state hashes, so the consensus states are
+    /// reconstructed from the counterparty's light blocks.
+    #[serde(default)]
+    pub counterparty_tm_rpc_urls: BTreeMap<String, String>,
+
+    /// The light client types deployed behind `ICS02Client`.
+    #[serde(default)]
+    pub light_clients: ClientRegistryConfig,
+
+    /// The caching of contract addresses derived from the `ICS26Router`.
+    #[serde(default)]
+    pub address_cache: AddressCacheConfig,
+
+    /// The format of the proofs returned by `query_ibc_proof`.
+    #[serde(default)]
+    pub proof_format: ProofFormat,
+
+    /// Whether to verify the proofs returned by the execution RPC against the state root of
+    /// the queried block before returning them. The state root is taken from the beacon chain
+    /// if `height_mode` is `beacon_slot`.
+    #[serde(default)]
+    pub verify_proofs: bool,
+
+    /// The packet event indexer. The indexer is disabled if this is not set.
+    ///
+    /// The indexer is only queried through the query server (see `query_server`), which is
+    /// served on its default address if the indexer is enabled and `query_server` is not set.
+    #[serde(default)]
+    pub indexer: Option<IndexerConfig>,
+
+    /// The batching of contract reads through `Multicall3`. Reads fall back to individual calls
+    /// if `Multicall3` is not deployed at the queried height.
+    #[serde(default)]
+    pub multicall: MulticallConfig,
+
+    /// Whether to skip probing how much historical state the execution RPC node serves at
+    /// startup. If skipped, the node is assumed to be archival.
+    #[serde(default)]
+    pub skip_capability_probe: bool,
+
+    /// The telemetry endpoint, serving metrics and health checks. It is disabled if this is not
+    /// set.
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
+
+    /// The JSON-RPC endpoint serving the queries that are not part of the voyager chain module
+    /// interface, i.e. the timestamp at a height, the unreceived packets and acknowledgements
+    /// and the indexed packets. Voyager never calls these, so they are only reachable through
+    /// this endpoint.
+    ///
+    /// It is served on its default address (`127.0.0.1:7881`) if this is not set but the
+    /// indexer is enabled, and disabled otherwise.
+    #[serde(default)]
+    pub query_server: Option<QueryServerConfig>,
+}
+
+impl Config {
+    /// The query server to serve, which defaults to [`QueryServerConfig::default`] if the
+    /// indexer is enabled, as the indexer can only be queried through it.
+    #[must_use]
+    pub fn query_server(&self) -> Option<QueryServerConfig> {
+        self.query_server
+            .clone()
+            .or_else(|| self.indexer.as_ref().map(|_| QueryServerConfig::default()))
+    }
+}
+
+impl ChainModule for Module {
+    type Config = Config;
+
+    async fn new(config: Self::Config, info: ChainModuleInfo) -> Result<Self, BoxDynError> {
+        if let Some(telemetry_config) = &config.telemetry {
+            ibc_eureka_telemetry::server::spawn(telemetry_config).await?;
+        }
+
+        let query_server = config.query_server();
+
+        let eth_provider = ProviderBuilder::new().on_client(
+            FailoverTransport::connect(
+                &config.eth_rpc_api,
+                config.rpc_failover.clone(),
+                config.rpc_quorum.clone(),
+            )
+            .await?
+            .with_retry(config.rpc_retry.clone())
+            .with_rate_limit(config.rpc_rate_limit.as_ref())
+            .into_client(),
+        );
+
+        let chain_id = eth_provider.get_chain_id().await?;
+
+        info.ensure_chain_id(U256::from(chain_id).to_string())?;
+
+        let ics26_router =
+            ics26_router::new(config.ics26_router_address.parse()?, eth_provider.clone());
+
+        let beacon_api_client = match (config.height_mode, config.eth_beacon_rpc_api) {
+            (_, Some(eth_beacon_rpc_api)) => {
+                let mut clients = vec![];
+                for url in eth_beacon_rpc_api.urls() {
+                    clients.push((url.clone(), BeaconApiClient::new(url.clone()).await?));
+                }
+
+                Some(
+                    FailoverBeaconClient::new(
+                        clients,
+                        config.rpc_failover.clone(),
+                        config.rpc_quorum.as_ref().map(|quorum| quorum.threshold),
+                    )?
+                    .with_retry(config.rpc_retry.clone())
+                    .with_rate_limit(config.rpc_rate_limit.as_ref()),
+                )
+            }
+            (HeightMode::BeaconSlot, None) => {
+                return Err(
+                    "`eth_beacon_rpc_api` is required if `height_mode` is `beacon_slot`".into(),
+                )
+            }
+            (HeightMode::ExecutionBlock, None) => None,
+        };
+
+        let finality = config
+            .finality
+            .unwrap_or(Finality::default_for(config.height_mode));
+        if !finality.supports(config.height_mode) {
+            return Err(format!(
+                "finality `{finality:?}` is not supported with height mode `{:?}`",
+                config.height_mode
+            )
+            .into());
+        }
+
+        let counterparty_tm_clients = config
+            .counterparty_tm_rpc_urls
+            .into_iter()
+            .map(|(chain_id, url)| Ok((chain_id, HttpClient::new(Url::from_str(&url)?)?)))
+            .collect::<Result<_, BoxDynError>>()?;
+
+        let address_cache = AddressCache::new(&config.address_cache);
+        if let Some(poll_interval) = config.address_cache.upgrade_poll_interval_secs {
+            let contracts = vec![
+                *ics26_router.address(),
+                ics26_router.IBC_STORE().call().await?._0,
+                ics26_router.ICS02_CLIENT().call().await?._0,
+            ];
+
+            address_cache.watch_upgrades(
+                eth_provider.clone(),
+                contracts,
+                Duration::from_secs(poll_interval),
+            );
+        }
+
+        let packet_indexer = config
+            .indexer
+            .map(|indexer_config| {
+                let indexer = PacketIndexer::new(
+                    &indexer_config,
+                    *ics26_router.address(),
+                    eth_provider.clone(),
+                )?;
+                indexer.spawn(Duration::from_secs(indexer_config.poll_interval_secs));
+
+                Ok::<_, ChainModuleError>(indexer)
+            })
+            .transpose()?;
+
+        let multicall = Multicall::new(&config.multicall, eth_provider.clone());
+
+        let node_capabilities = if config.skip_capability_probe {
+            NodeCapabilities::default()
+        } else {
+            capabilities::probe(&eth_provider, *ics26_router.address()).await?
+        };
+
+        let module = Self {
+            chain_id: ChainId::new(U256::from(chain_id).to_string()),
+            ics26_router,
+            eth_provider,
+            beacon_api_client,
+            height_mode: config.height_mode,
+            finality,
+            counterparty_tm_clients,
+            client_registry: ClientRegistry::new(config.light_clients)?,
+            address_cache,
+            proof_format: config.proof_format,
+            verify_proofs: config.verify_proofs,
+            packet_indexer,
+            multicall,
+            node_capabilities,
+            latest_execution_height: LatestHeightCache::default(),
+        };
+
+        health::register_checks(&module);
+
+        if let Some(query_server) = &query_server {
+            query::spawn(query_server, module.clone()).await?;
+        }
+
+        Ok(module)
+    }
+}
+
+impl Module {
+    /// Create a new height with the revision number set to the Ethereum revision number.
+    #[must_use]
+    pub const fn make_height(&self, height: u64) -> Height {
+        Height::new(height)
+    }
+
+    /// Get the beacon api client.
+    /// # Errors
+    /// Returns an error if no beacon api is configured.
+    pub fn beacon_api_client(&self) -> Result<&FailoverBeaconClient, ChainModuleError> {
+        self.beacon_api_client
+            .as_ref()
+            .ok_or(ChainModuleError::MissingBeaconApi)
+    }
+
+    /// Get the execution height of a beacon slot.
+    /// # Errors
+    /// Returns an error if the beacon api call fails.
+    pub async fn execution_height_of_beacon_slot(
+        &self,
+        slot: u64,
+    ) -> Result<u64, ChainModuleError> {
+        Ok(self
+            .beacon_api_client()?
+            .request(|client| async move { client.execution_height(BlockId::Slot(slot)).await })
+            .await?)
+    }
+
+    /// Get the execution height of a height, according to the [`HeightMode`].
+    /// # Errors
+    /// Returns an error if the beacon api call fails.
+    pub async fn execution_height(&self, height: Height) -> Result<u64, ChainModuleError> {
+        match self.height_mode {
+            HeightMode::BeaconSlot => self.execution_height_of_beacon_slot(height.height()).await,
+            HeightMode::ExecutionBlock => Ok(height.height()),
+        }
+    }
+
+    /// Query the latest height and its timestamp from a single snapshot, according to the
+    /// configured [`Finality`].
+    /// # Errors
+    /// Returns an error if the beacon api or rpc calls fail.
+    pub async fn query_latest_snapshot(&self) -> Result<LatestSnapshot, ChainModuleError> {
+        if let Some(block_tag) = self.finality.block_tag() {
+            let header = self.execution_block(block_tag).await?.header;
+
+            return Ok(LatestSnapshot {
+                height: header.number,
+                timestamp: header.timestamp,
+            });
+        }
+
+        let finality_update = self
+            .beacon_api_client()?
+            .request_quorum(|client| async move {
+                client
+                    .finality_update()
+                    .await
+                    .map(|finality_update| finality_update.data)
+            })
+            .await?;
+        let header = match self.finality {
+            Finality::FinalizedHeader => finality_update.finalized_header,
+            _ => finality_update.attested_header,
+        };
+
+        Ok(LatestSnapshot {
+            height: header.beacon.slot,
+            timestamp: header.execution.timestamp,
+        })
+    }
+
+    /// Query the execution timestamp at a height, in nanoseconds. This is the timestamp that
+    /// packet timeouts are checked against when proving at `height`.
+    /// # Errors
+    /// Returns an error if the beacon api or rpc calls fail, or if the timestamp overflows.
+    pub async fn query_timestamp_at_height(&self, height: Height) -> Result<u64, ChainModuleError> {
+        let timestamp = match self.height_mode {
+            HeightMode::BeaconSlot => {
+                let slot = height.height();
+
+                self.beacon_api_client()?
+                    .request(|client| async move { client.block(BlockId::Slot(slot)).await })
+                    .await?
+                    .data
+                    .message
+                    .body
+                    .execution_payload
+                    .timestamp
+            }
+            HeightMode::ExecutionBlock => {
+                self.execution_block(height.height().into())
+                    .await?
+                    .header
+                    .timestamp
+            }
+        };
+
+        timestamp_nanos(timestamp)
+    }
+
+    /// Get an execution block by number or tag.
+    /// # Errors
+    /// Returns an error if the rpc call fails or if the block does not exist.
+    pub async fn execution_block(
+        &self,
+        block: BlockNumberOrTag,
+    ) -> Result<Block, ChainModuleError> {
+        self.eth_provider
+            .get_block_by_number(block, BlockTransactionsKind::Hashes)
+            .await
+            .map_err(|err| ChainModuleError::from_rpc(err, block.as_number()))?
+            .ok_or_else(|| {
+                ChainModuleError::MalformedResponse(format!("execution block {block} not found"))
+            })
+    }
+
+    /// Get the number, hash and state root of the execution block at a height, according to
+    /// the [`HeightMode`].
+    /// # Errors
+    /// Returns an error if the beacon api or rpc calls fail.
+    pub async fn execution_block_of_height(
+        &self,
+        height: Height,
+    ) -> Result<(u64, B256, H256), ChainModuleError> {
+        match self.height_mode {
+            HeightMode::BeaconSlot => {
+                let slot = height.height();
+                let execution_payload = self
+                    .beacon_api_client()?
+                    .request_quorum(|client| async move {
+                        client
+                            .block(BlockId::Slot(slot))
+                            .await
+                            .map(|block| block.data.message.body.execution_payload)
+                    })
+                    .await?;
+
+                Ok((
+                    execution_payload.block_number,
+                    B256::from_slice(execution_payload.block_hash.as_ref()),
+                    execution_payload.state_root,
+                ))
+            }
+            HeightMode::ExecutionBlock => {
+                let block = self.execution_block(height.height().into()).await?;
+
+                Ok((
+                    block.header.number,
+                    block.header.hash,
+                    H256::from(block.header.state_root.0),
+                ))
+            }
+        }
+    }
+
+    /// Check that the execution RPC node serves state at `execution_height`, according to the
+    /// probed [`NodeCapabilities`]. This is a no-op for archival nodes.
+    /// # Errors
+    /// Returns [`ChainModuleError::StateUnavailable`] if the state at `execution_height` has
+    /// been pruned, or an error if the rpc call fails.
+    pub async fn ensure_state_available(
+        &self,
+        execution_height: u64,
+    ) -> Result<(), ChainModuleError> {
+        if self.node_capabilities.is_archival() {
+            return Ok(());
+        }
+
+        // the queried height exists, so it bounds the latest height from below. A cached latest
+        // height that is slightly stale is caught by the pruned state error of the node instead.
+        let latest_height = self
+            .latest_execution_height
+            .get(&self.eth_provider)
+            .await?
+            .max(execution_height);
+
+        match self
+            .node_capabilities
+            .oldest_available_height(latest_height)
+        {
+            Some(oldest_available_height) if execution_height < oldest_available_height => {
+                Err(ChainModuleError::StateUnavailable {
+                    execution_height,
+                    oldest_available_height,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the IBC store contract instance.
+    /// # Errors
+    /// Returns an error if the contract call fails.
+    pub async fn ibc_store_contract(
+        &self,
+    ) -> Result<storeInstance<BoxTransport, RootProvider<BoxTransport>>, ChainModuleError> {
+        let ibc_store_address = self
+            .address_cache
+            .get_or_fetch(CachedAddress::IbcStore, || async {
+                Ok(self.ics26_router.IBC_STORE().call().await?._0)
+            })
+            .await?;
+
+        Ok(ibc_store::new(ibc_store_address, self.eth_provider.clone()))
+    }
+
+    /// Get the address of the light client contract behind a client ID.
+    /// # Errors
+    /// Returns an error if the contract calls fail.
+    pub async fn light_client_address(
+        &self,
+        client_id: &ClientId,
+    ) -> Result<Address, ChainModuleError> {
+        let ics02_address = self
+            .address_cache
+            .get_or_fetch(CachedAddress::Ics02Client, || async {
+                Ok(self.ics26_router.ICS02_CLIENT().call().await?._0)
+            })
+            .await?;
+
+        self.address_cache
+            .get_or_fetch(
+                CachedAddress::LightClient(client_id.to_string()),
+                || async {
+                    let ics02_contract =
+                        ics02_client::new(ics02_address, self.eth_provider.clone());
+                    Ok(ics02_contract
+                        .getClient(client_id.to_string())
+                        .call()
+                        .await?
+                        ._0)
+                },
+            )
+            .await
+    }
+
+    /// Get the SP1 ICS07 Tendermint light client contract instance of a client.
+    /// # Errors
+    /// Returns an error if the contract calls fail.
+    pub async fn sp1_ics07_contract(
+        &self,
+        client_id: &ClientId,
+    ) -> Result<
+        sp1_ics07_tendermint::sp1_ics07_tendermintInstance<
+            BoxTransport,
+            RootProvider<BoxTransport>,
+        >,
+        ChainModuleError,
+    > {
+        Ok(sp1_ics07_tendermint::new(
+            self.light_client_address(client_id).await?,
+            self.eth_provider.clone(),
+        ))
+    }
+
+    /// Fetch the IBC state at a given height and path.
+    /// # Errors
+    /// Returns an error if the contract calls fail or if the requested path is not implemented
+    /// in IBC Eureka.
+    pub async fn fetch_ibc_state(
+        &self,
+        path: Path,
+        height: Height,
+    ) -> Result<Option<Bytes>, ChainModuleError> {
+        let execution_height = self.execution_height(height).await?;
+
+        self.fetch_ibc_state_at_execution_height(path, execution_height)
+            .await
+    }
+
+    /// Fetch the IBC state at a given execution height and path.
+    /// Unlike [`Self::fetch_ibc_state`], the height is always an execution block number,
+    /// regardless of the [`HeightMode`].
+    /// # Errors
+    /// Returns an error if the contract calls fail or if the requested path is not implemented
+    /// in IBC Eureka.
+    pub async fn fetch_ibc_state_at_execution_height(
+        &self,
+        path: Path,
+        execution_height: u64,
+    ) -> Result<Option<Bytes>, ChainModuleError> {
+        self.ensure_state_available(execution_height).await?;
+
+        Ok(match path {
+            Path::ClientState(path) => {
+                self.fetch_client_state(&path.client_id, execution_height)
+                    .await?
+            }
+            Path::Commitment(_) | Path::Acknowledgement(_) | Path::Receipt(_) => {
+                let commitment = self
+                    .ibc_store_contract()
+                    .await?
+                    .getCommitment(path.to_storage_key().into())
+                    .block(execution_height.into())
+                    .call()
+                    .await
+                    .map_err(|err| ChainModuleError::from_contract(err, Some(execution_height)))?
+                    ._0;
+
+                if commitment.is_zero() {
+                    return Ok(None);
+                }
+
+                Some(Bytes::from(commitment.abi_encode()))
+            }
+            Path::ClientConsensusState(path) => {
+                self.fetch_consensus_state(&path.client_id, path.height, execution_height)
+                    .await?
+            }
+            Path::Connection(_)
+            | Path::ChannelEnd(_)
+            | Path::NextSequenceSend(_)
+            | Path::NextSequenceRecv(_)
+            | Path::NextSequenceAck(_)
+            | Path::NextConnectionSequence(_)
+            | Path::NextClientSequence(_) => {
+                return Err(ChainModuleError::UnsupportedPath(path));
+            }
+        })
+    }
+
+    /// Fetch the client state of a client at `execution_height`.
+    /// # Errors
+    /// Returns an error if the light client type is unknown or if the contract calls fail.
+    pub async fn fetch_client_state(
+        &self,
+        client_id: &ClientId,
+        execution_height: u64,
+    ) -> Result<Option<Bytes>, ChainModuleError> {
+        match self.client_registry.light_client_type(client_id)? {
+            LightClientType::Sp1Ics07Tendermint => {
+                let client_state = self
+                    .sp1_ics07_contract(client_id)
+                    .await?
+                    .getClientState()
+                    .block(execution_height.into())
+                    .call()
+                    .await
+                    .map_err(|err| ChainModuleError::from_contract(err, Some(execution_height)))?
+                    ._0;
+
+                Ok(Some(Bytes::from(client_state.abi_encode())))
+            }
+            LightClientType::Mock => Ok(None),
+        }
+    }
+
+    /// Fetch the consensus state of a client at `trusted_height`, as stored at
+    /// `execution_height`.
+    ///
+    /// The SP1 ICS07 contract only stores the hash of each consensus state, so the consensus
+    /// state is rebuilt from the counterparty's light block at `trusted_height` and checked
+    /// against the stored hash.
+    /// # Errors
+    /// Returns an error if the contract or tendermint rpc calls fail, or if the reconstructed
+    /// consensus state does not match the stored hash.
+    pub async fn fetch_consensus_state(
+        &self,
+        client_id: &ClientId,
+        trusted_height: Height,
+        execution_height: u64,
+    ) -> Result<Option<Bytes>, ChainModuleError> {
+        match self.client_registry.light_client_type(client_id)? {
+            LightClientType::Sp1Ics07Tendermint => {}
+            LightClientType::Mock => return Ok(None),
+        }
+
+        // SP1 ICS07 stores consensus states by revision height, which is a `uint32`
+        let Ok(revision_height) = u32::try_from(trusted_height.height()) else {
+            return Ok(None);
+        };
+
+        let client = self.sp1_ics07_contract(client_id).await?;
+
+        let consensus_state_hash = client
+            .getConsensusStateHash(revision_height)
+            .block(execution_height.into())
+            .call()
+            .await
+            .map_err(|err| ChainModuleError::from_contract(err, Some(execution_height)))?
+            ._0;
+
+        if consensus_state_hash.is_zero() {
+            return Ok(None);
+        }
+
+        let chain_id = client
+            .getClientState()
+            .block(execution_height.into())
+            .call()
+            .await
+            .map_err(|err| ChainModuleError::from_contract(err, Some(execution_height)))?
+            ._0
+            .chainId;
+
+        let tm_client = self.counterparty_tm_clients.get(&chain_id).ok_or_else(|| {
+            ChainModuleError::MissingCounterpartyRpc {
+                chain_id: chain_id.clone(),
+            }
+        })?;
+
+        let start = Instant::now();
+        let light_block = tm_client.get_light_block(Some(revision_height)).await;
+        metrics().record_rpc_call(
+            "tendermint",
+            "light_block",
+            light_block.is_ok(),
+            start.elapsed(),
+        );
+        ibc_eureka_telemetry::health::health()
+            .record(&health::tendermint_rpc_dependency(&chain_id), &light_block);
+        let light_block =
+            light_block.map_err(|err| ChainModuleError::TendermintRpc(err.to_string()))?;
+
+        let consensus_state: ConsensusState = light_block.to_consensus_state().into();
+        let consensus_state_bytes = consensus_state.abi_encode();
+
+        if keccak256(&consensus_state_bytes) != consensus_state_hash {
+            return Err(ChainModuleError::MalformedResponse(format!(
+                "consensus state of `{chain_id}` at height {revision_height} does not match \
+                the stored consensus state hash {consensus_state_hash}"
+            )));
+        }
+
+        Ok(Some(Bytes::from(consensus_state_bytes)))
+    }
+
+    /// Query the storage proofs of multiple IBC paths at the same height, using a single
+    /// `eth_getProof` call.
+    /// # Errors
+    /// Returns an error if the rpc calls fail, if the response does not match the requested
+    /// paths or if proof verification is enabled and the proofs are invalid.
+    pub async fn query_ibc_proofs(
+        &self,
+        at: Height,
+        paths: Vec<Path>,
+    ) -> Result<BatchedStorageProof, ChainModuleError> {
+        // NOTE: When verifying, the proof is pinned to the block hash of the height, and
+        // checked against the state root of that block.
+        if self.verify_proofs {
+            let (execution_height, block_hash, state_root) =
+                self.execution_block_of_height(at).await?;
+
+            self.fetch_ibc_proofs(
+                execution_height,
+                EthBlockId::hash(block_hash),
+                Some(state_root),
+                paths,
+            )
+            .await
+        } else {
+            let execution_height = self.execution_height(at).await?;
+
+            self.fetch_ibc_proofs(execution_height, execution_height.into(), None, paths)
+                .await
+        }
+    }
+
+    /// Fetch the storage proofs of multiple IBC paths from a single `eth_getProof` call at
+    /// `block_id`, and verify them against `verify_against` if it is set.
+    async fn fetch_ibc_proofs(
+        &self,
+        execution_height: u64,
+        block_id: EthBlockId,
+        verify_against: Option<H256>,
+        paths: Vec<Path>,
+    ) -> Result<BatchedStorageProof, ChainModuleError> {
+        self.ensure_state_available(execution_height).await?;
+
+        // NOTE: The commitments are stored by the IBC store, not by the router
+        let ibc_store_address = *self.ibc_store_contract().await?.address();
+
+        let locations = paths
+            .iter()
+            .map(|path| commitment_location(path).to_be_bytes().into())
+            .collect();
+
+        let proof = self
+            .eth_provider
+            .get_proof(ibc_store_address, locations)
+            .block_id(block_id)
+            .await
+            .map_err(|err| ChainModuleError::from_rpc(err, Some(execution_height)))?;
+
+        let batch = BatchedStorageProof::new(execution_height, paths, proof)?;
+
+        if let Some(state_root) = verify_against {
+            verify::verify_batched_proof(state_root, ibc_store_address, &batch)?;
+        }
+
+        Ok(batch)
+    }
+
+    /// Query the storage proof of an IBC path together with the proof of the IBC store account
+    /// against the execution state root.
+    /// # Errors
+    /// Returns an error if the rpc calls fail, if the response does not match the requested
+    /// path or if proof verification is enabled and the proof is invalid.
+    pub async fn query_extended_ibc_proof(
+        &self,
+        at: Height,
+        path: Path,
+    ) -> Result<ExtendedStorageProof, ChainModuleError> {
+        // NOTE: The proof is always pinned to the block hash of the height, so that the returned
+        // state root is the one of the proven block even if the chain reorgs in between.
+        let (execution_height, block_hash, state_root) = self.execution_block_of_height(at).await?;
+
+        let BatchedStorageProof {
+            execution_height,
+            storage_hash,
+            account_proof,
+            proofs,
+        } = self
+            .fetch_ibc_proofs(
+                execution_height,
+                EthBlockId::hash(block_hash),
+                self.verify_proofs.then_some(state_root),
+                vec![path],
+            )
+            .await?;
+
+        let storage_proof = proof::single_storage_proof(proofs)?;
+
+        Ok(ExtendedStorageProof {
+            execution_height,
+            state_root,
+            storage_hash,
+            account_proof,
+            storage_proof,
+        })
+    }
+
+    /// Query a combined proof of multiple IBC paths at the same height.
+    /// # Errors
+    /// Returns an error if the rpc calls fail or if the response does not match the requested
+    /// paths.
+    pub async fn query_ibc_multiproof(
+        &self,
+        at: Height,
+        paths: Vec<Path>,
+    ) -> Result<StorageMultiProof, ChainModuleError> {
+        Ok(self.query_ibc_proofs(at, paths).await?.into_multiproof())
+    }
+}
+
+impl Module {
+    /// Get the packet indexer.
+    /// # Errors
+    /// Returns an error if the packet indexer is not enabled.
+    pub fn packet_indexer(&self) -> Result<&PacketIndexer, ChainModuleError> {
+        self.packet_indexer
+            .as_ref()
+            .ok_or(ChainModuleError::IndexerDisabled)
+    }
+
+    /// Query the sequences of the packets sent from a client that still have a commitment,
+    /// according to the packet indexer.
+    /// # Errors
+    /// Returns an error if the packet indexer is not enabled or if its database read fails.
+    pub fn query_pending_packet_commitments(
+        &self,
+        client_id: &ClientId,
+    ) -> Result<Vec<u64>, ChainModuleError> {
+        self.packet_indexer()?
+            .pending_commitments(&client_id.to_string())
+    }
+
+    /// Query the packets sent between two heights (inclusive), according to the packet
+    /// indexer.
+    /// # Errors
+    /// Returns an error if the packet indexer is not enabled, if the heights cannot be mapped to
+    /// execution heights or if the database read fails.
+    pub async fn query_packets_sent_between(
+        &self,
+        from: Height,
+        to: Height,
+    ) -> Result<Vec<IndexedPacket>, ChainModuleError> {
+        let packet_indexer = self.packet_indexer()?;
+
+        let from_block = self.execution_height(from).await?;
+        let to_block = self.execution_height(to).await?;
+
+        packet_indexer.sent_between(from_block, to_block)
+    }
+}
+
+impl Module {
+    /// Fetch the commitments of multiple commitment, acknowledgement or receipt paths from the
+    /// IBC store, all at the same execution height.
+    ///
+    /// The lookups are batched through `Multicall3` if it is enabled and deployed at
+    /// `execution_height`, and are sent as individual calls otherwise.
+    /// # Errors
+    /// Returns an error if any of the contract calls fail.
+    pub async fn fetch_commitments_at_execution_height(
+        &self,
+        paths: &[Path],
+        execution_height: u64,
+    ) -> Result<Vec<Option<B256>>, ChainModuleError> {
+        self.ensure_state_available(execution_height).await?;
+
+        let ibc_store = self.ibc_store_contract().await?;
+
+        let batched = match &self.multicall {
+            Some(multicall) => {
+                let calls = paths
+                    .iter()
+                    .map(|path| {
+                        let call_data = ibc_store
+                            .getCommitment(path.to_storage_key().into())
+                            .calldata()
+                            .clone();
+
+                        (*ibc_store.address(), call_data)
+                    })
+                    .collect();
+
+                multicall.aggregate(calls, execution_height).await?
+            }
+            None => None,
+        };
+
+        let commitments = match batched {
+            Some(return_data) => return_data
+                .iter()
+                .map(|data| {
+                    ibc_store::getCommitmentCall::abi_decode_returns(data, true)
+                        .map(|commitment| commitment._0)
+                        .map_err(|err| {
+                            ChainModuleError::MalformedResponse(format!(
+                                "unable to decode the return data of getCommitment: {err}"
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => {
+                try_join_all(paths.iter().map(|path| {
+                    let ibc_store = &ibc_store;
+                    async move {
+                        ibc_store
+                            .getCommitment(path.to_storage_key().into())
+                            .block(execution_height.into())
+                            .call()
+                            .await
+                            .map(|commitment| commitment._0)
+                            .map_err(|err| {
+                                ChainModuleError::from_contract(err, Some(execution_height))
+                            })
+                    }
+                }))
+                .await?
+            }
+        };
+
+        Ok(commitments
+            .into_iter()
+            .map(|commitment| Some(commitment).filter(|commitment| !commitment.is_zero()))
+            .collect())
+    }
+
+    /// Query which of the packets sent to `channel_id` on Ethereum with the given `sequences`
+    /// have not been received yet, i.e. have no receipt on Ethereum.
+    /// # Errors
+    /// Returns an error if the height cannot be mapped to an execution height or if the contract
+    /// calls fail.
+    pub async fn query_unreceived_packets(
+        &self,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequences: Vec<NonZeroU64>,
+    ) -> Result<Vec<NonZeroU64>, ChainModuleError> {
+        let paths = sequences
+            .iter()
+            .map(|&sequence| {
+                Path::Receipt(ReceiptPath {
+                    port_id: port_id.clone(),
+                    channel_id: channel_id.clone(),
+                    sequence,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.filter_sequences_by_commitment(height, &paths, sequences, false)
+            .await
+    }
+
+    /// Query which of the packets sent from `channel_id` on Ethereum with the given `sequences`
+    /// still have a packet commitment, i.e. their acknowledgements have not been relayed back
+    /// to Ethereum yet.
+    /// # Errors
+    /// Returns an error if the height cannot be mapped to an execution height or if the contract
+    /// calls fail.
+    pub async fn query_unreceived_acknowledgements(
+        &self,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequences: Vec<NonZeroU64>,
+    ) -> Result<Vec<NonZeroU64>, ChainModuleError> {
+        let paths = sequences
+            .iter()
+            .map(|&sequence| {
+                Path::Commitment(CommitmentPath {
+                    port_id: port_id.clone(),
+                    channel_id: channel_id.clone(),
+                    sequence,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        self.filter_sequences_by_commitment(height, &paths, sequences, true)
+            .await
+    }
+
+    /// Keep the sequences whose path has a commitment if `keep_committed` is set, or the ones
+    /// without a commitment otherwise.
+    async fn filter_sequences_by_commitment(
+        &self,
+        height: Height,
+        paths: &[Path],
+        sequences: Vec<NonZeroU64>,
+        keep_committed: bool,
+    ) -> Result<Vec<NonZeroU64>, ChainModuleError> {
+        let execution_height = self.execution_height(height).await?;
+
+        let commitments = self
+            .fetch_commitments_at_execution_height(paths, execution_height)
+            .await?;
+
+        Ok(sequences
+            .into_iter()
+            .zip(commitments)
+            .filter(|(_, commitment)| commitment.is_some() == keep_committed)
+            .map(|(sequence, _)| sequence)
+            .collect())
+    }
+}
+
+/// Convert a fetched commitment into a fixed length hash.
+fn commitment_to_h256(commitment: Bytes) -> Result<H256, ChainModuleError> {
+    let fixed_length_commitment: [u8; 32] =
+        commitment
+            .into_vec()
+            .try_into()
+            .map_err(|invalid: Vec<u8>| {
+                ChainModuleError::MalformedResponse(format!(
+                    "commitment should be 32 bytes long, but got {} bytes",
+                    invalid.len()
+                ))
+            })?;
+
+    Ok(fixed_length_commitment.into())
+}
+
+#[async_trait]
+impl ChainModuleServer for Module {
+    /// Query the latest finalized height of this chain.
+    async fn query_latest_height(&self, _: &Extensions) -> RpcResult<Height> {
+        let height = self.query_latest_snapshot().await?.height;
+        metrics().record_latest_height("eth-eureka", self.chain_id.as_str(), height);
+        ibc_eureka_telemetry::health::health().record_latest_height(self.chain_id.as_str(), height);
+
+        Ok(self.make_height(height))
+    }
+
+    /// Query the latest finalized timestamp of this chain, in nanoseconds.
+    async fn query_latest_timestamp(&self, _: &Extensions) -> RpcResult<i64> {
+        let timestamp = timestamp_nanos(self.query_latest_snapshot().await?.timestamp)?;
+
+        Ok(timestamp.try_into().map_err(|_| {
+            ChainModuleError::MalformedResponse(format!(
+                "timestamp {timestamp} does not fit in an i64"
+            ))
+        })?)
+    }
+
+    async fn query_client_prefix(&self, _: &Extensions, _raw_client_id: u32) -> RpcResult<String> {
+        Ok(self.client_registry.default_prefix().to_string())
+    }
+
+    async fn client_info(&self, _: &Extensions, client_id: ClientId) -> RpcResult<ClientInfo> {
+        let light_client_type = self.client_registry.light_client_type(&client_id)?;
+        let light_client_address = self.light_client_address(&client_id).await?;
+
+        Ok(ClientInfo {
+            client_type: ClientType::new(light_client_type.client_type()),
+            ibc_interface: IbcInterface::new(light_client_type.ibc_interface()),
+            metadata: json!({
+                "light_client_address": light_client_address,
+            }),
+        })
+    }
+
+    async fn query_client_state(
+        &self,
+        _: &Extensions,
+        height: Height,
+        client_id: ClientId,
+    ) -> RpcResult<Bytes> {
+        let path = Path::ClientState(ClientStatePath { client_id });
+
+        Ok(self
+            .fetch_ibc_state(path, height)
+            .await
+            .map(Option::unwrap_or_default)?)
+    }
+
+    async fn query_commitment(
+        &self,
+        _: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequence: NonZeroU64,
+    ) -> RpcResult<Option<H256>> {
+        let path = Path::Commitment(CommitmentPath {
+            port_id,
+            channel_id,
+            sequence,
+        });
+
+        Ok(self
+            .fetch_ibc_state(path, height)
+            .await?
+            .map(commitment_to_h256)
+            .transpose()?)
+    }
+
+    async fn query_acknowledgement(
+        &self,
+        _: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequence: NonZeroU64,
+    ) -> RpcResult<Option<H256>> {
+        let path = Path::Acknowledgement(AcknowledgementPath {
+            port_id,
+            channel_id,
+            sequence,
+        });
+
+        Ok(self
+            .fetch_ibc_state(path, height)
+            .await?
+            .map(commitment_to_h256)
+            .transpose()?)
+    }
+
+    async fn query_receipt(
+        &self,
+        _: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequence: NonZeroU64,
+    ) -> RpcResult<bool> {
+        let path = Path::Receipt(ReceiptPath {
+            port_id,
+            channel_id,
+            sequence,
+        });
+
+        Ok(self
+            .fetch_ibc_state(path, height)
+            .await
+            .map(|commitment| commitment.is_some())?)
+    }
+
+    async fn query_ibc_proof(&self, _: &Extensions, at: Height, path: Path) -> RpcResult<Value> {
+        let proof = match self.proof_format {
+            ProofFormat::Storage => {
+                let batch = self.query_ibc_proofs(at, vec![path]).await?;
+                serde_json::to_value(proof::single_storage_proof(batch.proofs)?)
+            }
+            ProofFormat::Extended => {
+                serde_json::to_value(self.query_extended_ibc_proof(at, path).await?)
+            }
+        };
+
+        Ok(proof.expect("serialization is infallible; qed;"))
+    }
+
+    async fn query_raw_unfinalized_trusted_client_state(
+        &self,
+        e: &Extensions,
+        client_id: ClientId,
+    ) -> RpcResult<RawClientState> {
+        let latest_execution_height = self
+            .eth_provider
+            .get_block_number()
+            .await
+            .map_err(ChainModuleError::from)?;
+
+        // NOTE: The latest execution height is not a beacon slot, so the state is queried at
+        // the execution height directly
+        let client_state = self
+            .fetch_ibc_state_at_execution_height(
+                ClientStatePath {
+                    client_id: client_id.clone(),
+                }
+                .into(),
+                latest_execution_height,
+            )
+            .await?
+            .ok_or_else(|| ChainModuleError::ClientStateNotFound {
+                client_id: client_id.to_string(),
+                execution_height: latest_execution_height,
+            })?;
+
+        let ClientInfo {
+            client_type,
+            ibc_interface,
+            metadata: _,
+        } = self.client_info(e, client_id).await?;
+
+        Ok(RawClientState {
+            client_type,
+            ibc_interface,
+            bytes: client_state,
+        })
+    }
+
+    async fn query_client_consensus_state(
+        &self,
+        _: &Extensions,
+        height: Height,
+        client_id: ClientId,
+        trusted_height: Height,
+    ) -> RpcResult<Bytes> {
+        let path = Path::ClientConsensusState(ClientConsensusStatePath {
+            client_id,
+            height: trusted_height,
+        });
+
+        Ok(self
+            .fetch_ibc_state(path, height)
+            .await
+            .map(Option::unwrap_or_default)?)
+    }
+
+    async fn query_connection(
+        &self,
+        _: &Extensions,
+        _height: Height,
+        connection_id: ConnectionId,
+    ) -> RpcResult<Option<ConnectionEnd>> {
+        // NOTE: ibc_eureka does not support connections
+        Err(
+            ChainModuleError::UnsupportedPath(Path::Connection(ConnectionPath { connection_id }))
+                .into(),
+        )
+    }
+
+    async fn query_channel(
+        &self,
+        _: &Extensions,
+        _height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+    ) -> RpcResult<Option<Channel>> {
+        // NOTE: ibc_eureka does not support channels
+        Err(
+            ChainModuleError::UnsupportedPath(Path::ChannelEnd(ChannelEndPath {
+                port_id,
+                channel_id,
+            }))
+            .into(),
+        )
+    }
+
+    async fn query_next_sequence_send(
+        &self,
+        _: &Extensions,
+        _height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+    ) -> RpcResult<u64> {
+        // NOTE: ibc_eureka does not support provable sequences
+        Err(
+            ChainModuleError::UnsupportedPath(Path::NextSequenceSend(NextSequenceSendPath {
+                port_id,
+                channel_id,
+            }))
+            .into(),
+        )
+    }
+
+    async fn query_next_sequence_recv(
+        &self,
+        _: &Extensions,
+        _height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+    ) -> RpcResult<u64> {
+        // NOTE: ibc_eureka does not support provable sequences
+        Err(
+            ChainModuleError::UnsupportedPath(Path::NextSequenceRecv(NextSequenceRecvPath {
+                port_id,
+                channel_id,
+            }))
+            .into(),
+        )
+    }
+
+    async fn query_next_sequence_ack(
+        &self,
+        _: &Extensions,
+        _height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+    ) -> RpcResult<u64> {
+        // NOTE: ibc_eureka does not support provable sequences
+        Err(
+            ChainModuleError::UnsupportedPath(Path::NextSequenceAck(NextSequenceAckPath {
+                port_id,
+                channel_id,
+            }))
+            .into(),
+        )
+    }
+
+    async fn query_next_connection_sequence(
+        &self,
+        _: &Extensions,
+        _height: Height,
+    ) -> RpcResult<u64> {
+        // NOTE: ibc_eureka does not support provable sequences
+        Err(
+            ChainModuleError::UnsupportedPath(Path::NextConnectionSequence(
+                NextConnectionSequencePath {},
+            ))
+            .into(),
+        )
+    }
+
+    async fn query_next_client_sequence(&self, _: &Extensions, _height: Height) -> RpcResult<u64> {
+        // NOTE: ibc_eureka does not support provable sequences
+        Err(
+            ChainModuleError::UnsupportedPath(Path::NextClientSequence(NextClientSequencePath {}))
+                .into(),
+        )
+    }
+}
@@ -0,0 +1,167 @@
+//! Batched contract reads through `Multicall3`
+
+use std::sync::{Arc, RwLock};
+
+use alloy::{
+    eips::BlockId,
+    primitives::{address, Address, Bytes},
+    providers::{Provider, RootProvider},
+    transports::BoxTransport,
+};
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::error::ChainModuleError;
+
+/// The address `Multicall3` is deployed at on most EVM chains
+pub const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+
+alloy::sol! {
+    #[sol(rpc)]
+    #[allow(missing_docs, clippy::pedantic)]
+    interface IMulticall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct CallResult {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls)
+            external
+            payable
+            returns (CallResult[] memory returnData);
+    }
+}
+
+/// The configuration for batching contract reads through `Multicall3`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+#[allow(clippy::module_name_repetitions)]
+pub struct MulticallConfig {
+    /// Whether to batch contract reads through `Multicall3`.
+    pub enabled: bool,
+    /// The address of the `Multicall3` contract.
+    pub address: Address,
+    /// The maximum number of calls to aggregate into a single `eth_call`.
+    pub max_batch_size: usize,
+}
+
+impl Default for MulticallConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            address: MULTICALL3_ADDRESS,
+            max_batch_size: 500,
+        }
+    }
+}
+
+/// Aggregates contract reads at a pinned block into `Multicall3.aggregate3` calls
+#[derive(Debug, Clone)]
+pub struct Multicall {
+    address: Address,
+    provider: RootProvider<BoxTransport>,
+    max_batch_size: usize,
+    /// The lowest execution height `Multicall3` is known to be deployed at
+    deployed_since: Arc<RwLock<Option<u64>>>,
+}
+
+impl Multicall {
+    /// Create a new `Multicall3` batcher, or `None` if batching is disabled.
+    #[must_use]
+    pub fn new(config: &MulticallConfig, provider: RootProvider<BoxTransport>) -> Option<Self> {
+        config.enabled.then(|| Self {
+            address: config.address,
+            provider,
+            max_batch_size: config.max_batch_size.max(1),
+            deployed_since: Arc::default(),
+        })
+    }
+
+    /// Whether `Multicall3` is deployed at `execution_height`.
+    /// # Errors
+    /// Returns an error if the rpc call fails.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub async fn is_deployed(&self, execution_height: u64) -> Result<bool, ChainModuleError> {
+        let deployed_since = *self.deployed_since.read().expect("lock is poisoned");
+        if deployed_since.is_some_and(|deployed_since| deployed_since <= execution_height) {
+            return Ok(true);
+        }
+
+        let code = self
+            .provider
+            .get_code_at(self.address)
+            .block_id(BlockId::number(execution_height))
+            .await
+            .map_err(|err| ChainModuleError::from_rpc(err, Some(execution_height)))?;
+
+        if code.is_empty() {
+            debug!(
+                address = %self.address,
+                execution_height,
+                "multicall3 is not deployed"
+            );
+            return Ok(false);
+        }
+
+        let mut deployed_since = self.deployed_since.write().expect("lock is poisoned");
+        *deployed_since = Some(deployed_since.map_or(execution_height, |deployed_since| {
+            deployed_since.min(execution_height)
+        }));
+
+        Ok(true)
+    }
+
+    /// Execute `calls` (target and calldata) at `execution_height`, returning the return data of
+    /// each call in order, or `None` if `Multicall3` is not deployed at that height.
+    /// # Errors
+    /// Returns an error if the rpc calls fail or if any of the calls reverts.
+    pub async fn aggregate(
+        &self,
+        calls: Vec<(Address, Bytes)>,
+        execution_height: u64,
+    ) -> Result<Option<Vec<Bytes>>, ChainModuleError> {
+        if !self.is_deployed(execution_height).await? {
+            return Ok(None);
+        }
+
+        let multicall = IMulticall3::new(self.address, self.provider.clone());
+
+        let batches = try_join_all(calls.chunks(self.max_batch_size).map(|chunk| {
+            let calls = chunk
+                .iter()
+                .map(|(target, call_data)| IMulticall3::Call3 {
+                    target: *target,
+                    allowFailure: false,
+                    callData: call_data.clone(),
+                })
+                .collect::<Vec<_>>();
+            let multicall = &multicall;
+
+            async move {
+                multicall
+                    .aggregate3(calls)
+                    .block(execution_height.into())
+                    .call()
+                    .await
+                    .map_err(|err| ChainModuleError::from_contract(err, Some(execution_height)))
+            }
+        }))
+        .await?;
+
+        Ok(Some(
+            batches
+                .into_iter()
+                .flat_map(|batch| batch.returnData)
+                .map(|result| result.returnData)
+                .collect(),
+        ))
+    }
+}
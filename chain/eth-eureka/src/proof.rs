@@ -0,0 +1,186 @@
+//! IBC proofs served by the Ethereum Eureka chain module
+
+use std::collections::BTreeSet;
+
+use alloy::rpc::types::{EIP1186AccountProofResponse, EIP1186StorageProof};
+use ethereum_light_client_types::StorageProof;
+use ibc_eureka_solidity::ibc_store::IBC_STORE_COMMITMENTS_SLOT;
+use ibc_eureka_union_ext::path::IbcEurekaPathExt;
+use serde::{Deserialize, Serialize};
+use unionlabs::{ethereum::ibc_commitment_key, hash::H256, ics24::Path, uint::U256};
+
+use crate::error::ChainModuleError;
+
+/// The format of the proofs returned by `query_ibc_proof`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofFormat {
+    /// A bare [`StorageProof`] of the commitment
+    #[default]
+    Storage,
+    /// An [`ExtendedStorageProof`], which also proves the IBC store's storage root against the
+    /// execution state root
+    Extended,
+}
+
+/// Storage proofs of multiple IBC paths, all fetched from a single `eth_getProof` call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchedStorageProof {
+    /// The execution height the proofs were fetched at
+    pub execution_height: u64,
+    /// The storage root of the IBC store account
+    pub storage_hash: H256,
+    /// The proof of the IBC store account against the execution state root
+    pub account_proof: Vec<Vec<u8>>,
+    /// The storage proof of each requested path, in request order
+    pub proofs: Vec<PathStorageProof>,
+}
+
+/// A storage proof of an IBC path together with the proof of the IBC store account, which allows
+/// verifying the commitment against the execution state root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtendedStorageProof {
+    /// The execution height the proof was fetched at
+    pub execution_height: u64,
+    /// The execution state root at `execution_height`
+    pub state_root: H256,
+    /// The storage root of the IBC store account
+    pub storage_hash: H256,
+    /// The proof of the IBC store account against `state_root`
+    pub account_proof: Vec<Vec<u8>>,
+    /// The proof of the commitment against `storage_hash`
+    pub storage_proof: StorageProof,
+}
+
+/// The storage proof of a single IBC path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathStorageProof {
+    /// The proven path
+    pub path: Path,
+    /// The storage proof of the path's commitment
+    pub proof: StorageProof,
+}
+
+/// A combined proof of multiple storage slots, sharing trie nodes between the proven slots
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageMultiProof {
+    /// The execution height the proof was fetched at
+    pub execution_height: u64,
+    /// The proven storage keys
+    pub keys: Vec<U256>,
+    /// The storage values, in the same order as `keys`
+    pub values: Vec<U256>,
+    /// The deduplicated trie nodes required to prove all keys
+    pub nodes: Vec<Vec<u8>>,
+}
+
+impl BatchedStorageProof {
+    /// Build a batched proof from an `eth_getProof` response for `paths`.
+    /// # Errors
+    /// Returns an error if the returned proofs do not match the requested paths.
+    pub fn new(
+        execution_height: u64,
+        paths: Vec<Path>,
+        response: EIP1186AccountProofResponse,
+    ) -> Result<Self, ChainModuleError> {
+        Ok(Self {
+            execution_height,
+            storage_hash: H256::from(response.storage_hash.0),
+            account_proof: response
+                .account_proof
+                .into_iter()
+                .map(|bytes| bytes.to_vec())
+                .collect(),
+            proofs: path_storage_proofs(paths, response.storage_proof)?,
+        })
+    }
+
+    /// Combine the individual proofs into a single [`StorageMultiProof`].
+    #[must_use]
+    pub fn into_multiproof(self) -> StorageMultiProof {
+        let mut nodes = BTreeSet::new();
+        let (keys, values) = self
+            .proofs
+            .into_iter()
+            .map(|PathStorageProof { proof, .. }| {
+                nodes.extend(proof.proof);
+                (proof.key, proof.value)
+            })
+            .unzip();
+
+        StorageMultiProof {
+            execution_height: self.execution_height,
+            keys,
+            values,
+            nodes: nodes.into_iter().collect(),
+        }
+    }
+}
+
+/// Take the only proof out of a batch that was requested for a single path.
+/// # Errors
+/// Returns an error if the batch does not contain exactly one proof.
+pub fn single_storage_proof(
+    proofs: Vec<PathStorageProof>,
+) -> Result<StorageProof, ChainModuleError> {
+    match <[_; 1]>::try_from(proofs) {
+        Ok([proof]) => Ok(proof.proof),
+        Err(invalid) => Err(ChainModuleError::MalformedResponse(format!(
+            "received invalid response from eth_getProof, expected length of 1 but got {}",
+            invalid.len()
+        ))),
+    }
+}
+
+/// The storage slot of the commitment of `path` in the IBC store.
+#[must_use]
+pub fn commitment_location(path: &Path) -> U256 {
+    ibc_commitment_key(
+        path.to_storage_key().into(),
+        IBC_STORE_COMMITMENTS_SLOT.into(),
+    )
+}
+
+/// Convert the storage proofs returned by `eth_getProof` into [`PathStorageProof`]s, checking
+/// that each proof matches the requested path.
+/// # Errors
+/// Returns an error if the returned proofs do not match the requested paths.
+pub fn path_storage_proofs(
+    paths: Vec<Path>,
+    storage_proofs: Vec<EIP1186StorageProof>,
+) -> Result<Vec<PathStorageProof>, ChainModuleError> {
+    if paths.len() != storage_proofs.len() {
+        return Err(ChainModuleError::MalformedResponse(format!(
+            "received invalid response from eth_getProof, expected length of {} but got {}",
+            paths.len(),
+            storage_proofs.len()
+        )));
+    }
+
+    paths
+        .into_iter()
+        .zip(storage_proofs)
+        .map(|(path, proof)| {
+            let proof = StorageProof {
+                key: U256::from_be_bytes(proof.key.0 .0),
+                value: U256::from_be_bytes(proof.value.to_be_bytes()),
+                proof: proof
+                    .proof
+                    .into_iter()
+                    .map(|bytes| bytes.to_vec())
+                    .collect(),
+            };
+
+            let expected_key = commitment_location(&path);
+            if proof.key != expected_key {
+                return Err(ChainModuleError::MalformedResponse(format!(
+                    "received proof for key {} from eth_getProof, but expected {expected_key} \
+                    for path `{path}`",
+                    proof.key
+                )));
+            }
+
+            Ok(PathStorageProof { path, proof })
+        })
+        .collect()
+}
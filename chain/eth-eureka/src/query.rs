@@ -0,0 +1,140 @@
+//! Queries served in addition to the voyager chain module interface
+//!
+//! Voyager only calls the `ChainModuleServer` methods, so the other queries of the module are
+//! served on a separate JSON-RPC endpoint.
+
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    num::NonZeroU64,
+};
+
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    server::Server,
+};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use unionlabs::{
+    ibc::core::client::height::Height,
+    id::{ChannelId, ClientId, PortId},
+};
+
+use crate::{indexer::IndexedPacket, Module};
+
+/// The configuration of the query endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(clippy::module_name_repetitions)]
+pub struct QueryServerConfig {
+    /// The address to serve the JSON-RPC query endpoint on.
+    pub listen_address: SocketAddr,
+}
+
+impl Default for QueryServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_address: (Ipv4Addr::LOCALHOST, 7881).into(),
+        }
+    }
+}
+
+/// The queries of the Ethereum Eureka chain module that are not part of the voyager chain
+/// module interface
+#[rpc(server, namespace = "eureka")]
+pub trait EurekaQuery {
+    /// The execution timestamp at `height`, in nanoseconds. This is the timestamp that packet
+    /// timeouts are checked against when proving at `height`.
+    #[method(name = "timestampAtHeight")]
+    async fn timestamp_at_height(&self, height: Height) -> RpcResult<u64>;
+
+    /// The sequences of the packets sent from `client_id` that still have a commitment,
+    /// according to the packet indexer.
+    #[method(name = "pendingPacketCommitments")]
+    async fn pending_packet_commitments(&self, client_id: ClientId) -> RpcResult<Vec<u64>>;
+
+    /// The packets sent between two heights (inclusive), according to the packet indexer.
+    #[method(name = "packetsSentBetween")]
+    async fn packets_sent_between(&self, from: Height, to: Height)
+        -> RpcResult<Vec<IndexedPacket>>;
+
+    /// Which of the packets sent to `channel_id` on Ethereum with the given `sequences` have
+    /// not been received yet at `height`.
+    #[method(name = "unreceivedPackets")]
+    async fn unreceived_packets(
+        &self,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequences: Vec<NonZeroU64>,
+    ) -> RpcResult<Vec<NonZeroU64>>;
+
+    /// Which of the packets sent from `channel_id` on Ethereum with the given `sequences` have
+    /// not had their acknowledgement relayed back yet at `height`.
+    #[method(name = "unreceivedAcknowledgements")]
+    async fn unreceived_acknowledgements(
+        &self,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequences: Vec<NonZeroU64>,
+    ) -> RpcResult<Vec<NonZeroU64>>;
+}
+
+#[async_trait]
+impl EurekaQueryServer for Module {
+    async fn timestamp_at_height(&self, height: Height) -> RpcResult<u64> {
+        Ok(self.query_timestamp_at_height(height).await?)
+    }
+
+    async fn pending_packet_commitments(&self, client_id: ClientId) -> RpcResult<Vec<u64>> {
+        Ok(self.query_pending_packet_commitments(&client_id)?)
+    }
+
+    async fn packets_sent_between(
+        &self,
+        from: Height,
+        to: Height,
+    ) -> RpcResult<Vec<IndexedPacket>> {
+        Ok(self.query_packets_sent_between(from, to).await?)
+    }
+
+    async fn unreceived_packets(
+        &self,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequences: Vec<NonZeroU64>,
+    ) -> RpcResult<Vec<NonZeroU64>> {
+        Ok(self
+            .query_unreceived_packets(height, port_id, channel_id, sequences)
+            .await?)
+    }
+
+    async fn unreceived_acknowledgements(
+        &self,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequences: Vec<NonZeroU64>,
+    ) -> RpcResult<Vec<NonZeroU64>> {
+        Ok(self
+            .query_unreceived_acknowledgements(height, port_id, channel_id, sequences)
+            .await?)
+    }
+}
+
+/// Serve the [`EurekaQueryServer`] queries of `module` on the configured address, in the
+/// background.
+/// # Errors
+/// Returns an error if the address cannot be bound to.
+pub async fn spawn(config: &QueryServerConfig, module: Module) -> std::io::Result<()> {
+    let server = Server::builder().build(config.listen_address).await?;
+    info!(listen_address = %config.listen_address, "serving eureka queries");
+
+    // the server is stopped once its handle is dropped
+    let handle = server.start(module.into_rpc());
+    tokio::spawn(handle.stopped());
+
+    Ok(())
+}
@@ -0,0 +1,582 @@
+//! Tests of the [`Module`] queries against stand-in execution and beacon endpoints
+
+use std::{collections::BTreeMap, num::NonZeroU64};
+
+use alloy::{
+    primitives::{address, Address, B256},
+    providers::{ProviderBuilder, RootProvider},
+    sol_types::{SolCall, SolValue},
+    transports::BoxTransport,
+};
+use beacon_api::client::BeaconApiClient;
+use ibc_eureka_rpc::{
+    beacon::FailoverBeaconClient,
+    config::{Endpoints, FailoverConfig},
+    execution::FailoverTransport,
+};
+use ibc_eureka_solidity::{
+    ibc_store::store as ibc_store, ics02::client as ics02_client, ics26::router as ics26_router,
+};
+use ibc_eureka_test_utils::{
+    eth::{block_number, eth_call_input, method_not_found, selector, JsonRpcError},
+    stand_in::StandIn,
+};
+use ibc_eureka_union_ext::path::IbcEurekaPathExt;
+use jsonrpsee::Extensions;
+use serde_json::{json, Value};
+use sp1_ics07_tendermint_solidity::{
+    sp1_ics07_tendermint,
+    IICS02ClientMsgs::Height as SolHeight,
+    IICS07TendermintMsgs::{ClientState, TrustThreshold},
+};
+use unionlabs::{
+    ibc::core::client::height::Height,
+    ics24::{ClientStatePath, CommitmentPath, Path, ReceiptPath},
+    id::{ChannelId, ClientId, PortId},
+};
+use voyager_message::{core::ChainId, module::ChainModuleServer, FATAL_JSONRPC_ERROR_CODE};
+
+use crate::{
+    cache::{AddressCache, AddressCacheConfig},
+    capabilities::{self, LatestHeightCache, NodeCapabilities},
+    client::{ClientRegistry, ClientRegistryConfig, LightClientType},
+    error::ChainModuleError,
+    height::{Finality, HeightMode},
+    multicall::{IMulticall3, Multicall, MulticallConfig, MULTICALL3_ADDRESS},
+    proof::ProofFormat,
+    Config, Module,
+};
+
+pub const ROUTER_ADDRESS: Address = address!("1000000000000000000000000000000000000026");
+pub const IBC_STORE_ADDRESS: Address = address!("1000000000000000000000000000000000000024");
+pub const ICS02_ADDRESS: Address = address!("1000000000000000000000000000000000000002");
+pub const LIGHT_CLIENT_ADDRESS: Address = address!("1000000000000000000000000000000000000007");
+
+/// The latest execution block number served by [`execution_stand_in`]
+pub const LATEST_EXECUTION_HEIGHT: u64 = 100;
+
+/// A module talking to the `execution` stand-in, and to the `beacon` stand-in if any. Contract
+/// reads use individual calls, and the execution node is assumed to be archival.
+pub async fn module(
+    execution: &StandIn,
+    beacon: Option<&StandIn>,
+    height_mode: HeightMode,
+) -> Module {
+    let eth_provider = eth_provider(execution).await;
+
+    let beacon_api_client = match beacon {
+        Some(beacon) => Some(
+            FailoverBeaconClient::new(
+                vec![(
+                    beacon.url(),
+                    BeaconApiClient::new(beacon.url()).await.unwrap(),
+                )],
+                FailoverConfig::default(),
+                None,
+            )
+            .unwrap(),
+        ),
+        None => None,
+    };
+
+    let mut light_clients = ClientRegistryConfig::default();
+    light_clients
+        .prefixes
+        .insert("mock-client".to_string(), LightClientType::Mock);
+
+    Module {
+        chain_id: ChainId::new("1".to_string()),
+        ics26_router: ics26_router::new(ROUTER_ADDRESS, eth_provider.clone()),
+        eth_provider,
+        beacon_api_client,
+        height_mode,
+        finality: Finality::default_for(height_mode),
+        counterparty_tm_clients: BTreeMap::new(),
+        client_registry: ClientRegistry::new(light_clients).unwrap(),
+        address_cache: AddressCache::new(&AddressCacheConfig::default()),
+        proof_format: ProofFormat::default(),
+        verify_proofs: false,
+        packet_indexer: None,
+        multicall: None,
+        node_capabilities: NodeCapabilities::default(),
+        latest_execution_height: LatestHeightCache::default(),
+    }
+}
+
+/// A provider talking to the `execution` stand-in.
+pub async fn eth_provider(execution: &StandIn) -> RootProvider<BoxTransport> {
+    ProviderBuilder::new().on_client(
+        FailoverTransport::connect(
+            &Endpoints::Single(execution.url()),
+            FailoverConfig::default(),
+            None,
+        )
+        .await
+        .unwrap()
+        .into_client(),
+    )
+}
+
+/// A stand-in execution RPC at [`LATEST_EXECUTION_HEIGHT`], answering `eth_call`s with
+/// `contracts` and serving the addresses derived from the router. Only the `deployed` contracts
+/// have code.
+pub async fn execution_stand_in<F>(deployed: Vec<Address>, contracts: F) -> StandIn
+where
+    F: Fn(Address, &[u8], &Value) -> Result<Vec<u8>, JsonRpcError> + Send + Sync + 'static,
+{
+    StandIn::eth_json_rpc(
+        move |method, params| match method {
+            "eth_blockNumber" => Ok(json!(format!("{LATEST_EXECUTION_HEIGHT:#x}"))),
+            "eth_getCode" => {
+                let address = params[0].as_str().unwrap().parse::<Address>().unwrap();
+
+                Ok(json!(if deployed.contains(&address) {
+                    "0x6080604052"
+                } else {
+                    "0x"
+                }))
+            }
+            _ => Err(method_not_found(method)),
+        },
+        move |to, input, block| match (to, selector(input)) {
+            (ROUTER_ADDRESS, ics26_router::IBC_STORECall::SELECTOR) => Ok(
+                ics26_router::IBC_STORECall::abi_encode_returns(&(IBC_STORE_ADDRESS,)),
+            ),
+            (ROUTER_ADDRESS, ics26_router::ICS02_CLIENTCall::SELECTOR) => Ok(
+                ics26_router::ICS02_CLIENTCall::abi_encode_returns(&(ICS02_ADDRESS,)),
+            ),
+            (ICS02_ADDRESS, ics02_client::getClientCall::SELECTOR) => Ok(
+                ics02_client::getClientCall::abi_encode_returns(&(LIGHT_CLIENT_ADDRESS,)),
+            ),
+            _ => contracts(to, input, block),
+        },
+    )
+    .await
+}
+
+/// A stand-in beacon API that does not serve any slot.
+pub async fn beacon_stand_in() -> StandIn {
+    StandIn::serve(|_| (404, json!({ "code": 404, "message": "not found" }))).await
+}
+
+fn sp1_client_state() -> ClientState {
+    ClientState {
+        chainId: "cosmoshub-4".to_string(),
+        trustLevel: TrustThreshold {
+            numerator: 1,
+            denominator: 3,
+        },
+        latestHeight: SolHeight {
+            revisionNumber: 4,
+            revisionHeight: 1_000,
+        },
+        trustingPeriod: 1_209_600,
+        unbondingPeriod: 1_814_400,
+        isFrozen: false,
+        zkAlgorithm: 1,
+    }
+}
+
+#[tokio::test]
+async fn raw_unfinalized_client_state_is_queried_at_the_latest_execution_block() {
+    let execution = execution_stand_in(vec![], |to, input, _| match (to, selector(input)) {
+        (LIGHT_CLIENT_ADDRESS, sp1_ics07_tendermint::getClientStateCall::SELECTOR) => Ok(
+            sp1_ics07_tendermint::getClientStateCall::abi_encode_returns(&(sp1_client_state(),)),
+        ),
+        _ => Err((3, "execution reverted".to_string())),
+    })
+    .await;
+    let beacon = beacon_stand_in().await;
+
+    // the latest execution block (100) is not a beacon slot: mapping it through the beacon api
+    // would query the state of a different block, or no block at all
+    let module = module(&execution, Some(&beacon), HeightMode::BeaconSlot).await;
+
+    let raw_client_state = module
+        .query_raw_unfinalized_trusted_client_state(
+            &Extensions::new(),
+            "07-tendermint-0".parse::<ClientId>().unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        raw_client_state.bytes.into_vec(),
+        sp1_client_state().abi_encode()
+    );
+
+    let client_state_calls = execution
+        .json_rpc_calls("eth_call")
+        .into_iter()
+        .filter(|params| {
+            let (to, input) = eth_call_input(params);
+            to == LIGHT_CLIENT_ADDRESS
+                && selector(&input) == sp1_ics07_tendermint::getClientStateCall::SELECTOR
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(client_state_calls.len(), 1);
+    assert_eq!(
+        block_number(&client_state_calls[0][1]),
+        LATEST_EXECUTION_HEIGHT
+    );
+
+    assert!(beacon.requests().is_empty());
+}
+
+#[tokio::test]
+async fn missing_raw_unfinalized_client_state_is_an_error() {
+    let execution =
+        execution_stand_in(vec![], |_, _, _| Err((3, "execution reverted".to_string()))).await;
+    let beacon = beacon_stand_in().await;
+    let module = module(&execution, Some(&beacon), HeightMode::BeaconSlot).await;
+
+    // mock clients do not expose a client state
+    let client_id = "mock-client-0".parse::<ClientId>().unwrap();
+    let client_state = module
+        .fetch_ibc_state_at_execution_height(
+            ClientStatePath {
+                client_id: client_id.clone(),
+            }
+            .into(),
+            LATEST_EXECUTION_HEIGHT,
+        )
+        .await;
+    assert!(matches!(client_state, Ok(None)));
+
+    let err = module
+        .query_raw_unfinalized_trusted_client_state(&Extensions::new(), client_id)
+        .await
+        .unwrap_err();
+
+    let data = serde_json::from_str::<Value>(err.data().unwrap().get()).unwrap();
+    assert_eq!(err.code(), FATAL_JSONRPC_ERROR_CODE);
+    assert_eq!(data["kind"], "client_state_not_found");
+    assert!(beacon.requests().is_empty());
+}
+
+/// A stand-in execution RPC where only the paths in `committed` have a commitment in the IBC
+/// store, serving `getCommitment` both directly and through `Multicall3` if `multicall` is set.
+async fn ibc_store_stand_in(committed: Vec<Path>, multicall: bool) -> StandIn {
+    let committed = committed
+        .iter()
+        .map(|path| B256::from(path.to_storage_key()))
+        .collect::<Vec<_>>();
+    let commitment = move |call_data: &[u8]| {
+        assert_eq!(selector(call_data), ibc_store::getCommitmentCall::SELECTOR);
+
+        let value = if committed.contains(&B256::from_slice(&call_data[4..36])) {
+            B256::repeat_byte(1)
+        } else {
+            B256::ZERO
+        };
+
+        ibc_store::getCommitmentCall::abi_encode_returns(&(value,))
+    };
+
+    let deployed = if multicall {
+        vec![MULTICALL3_ADDRESS]
+    } else {
+        vec![]
+    };
+
+    execution_stand_in(deployed, move |to, input, _| match to {
+        IBC_STORE_ADDRESS => Ok(commitment(input)),
+        MULTICALL3_ADDRESS => {
+            let results = IMulticall3::aggregate3Call::abi_decode(input, true)
+                .unwrap()
+                .calls
+                .into_iter()
+                .map(|call| {
+                    assert_eq!(call.target, IBC_STORE_ADDRESS);
+
+                    IMulticall3::CallResult {
+                        success: true,
+                        returnData: commitment(&call.callData).into(),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            Ok(IMulticall3::aggregate3Call::abi_encode_returns(&(results,)))
+        }
+        _ => Err((3, "execution reverted".to_string())),
+    })
+    .await
+}
+
+/// The `eth_call`s made to `contract`, and the block number of each.
+fn calls_to(stand_in: &StandIn, contract: Address) -> Vec<u64> {
+    stand_in
+        .json_rpc_calls("eth_call")
+        .into_iter()
+        .filter(|params| eth_call_input(params).0 == contract)
+        .map(|params| block_number(&params[1]))
+        .collect()
+}
+
+fn sequences(sequences: impl IntoIterator<Item = u64>) -> Vec<NonZeroU64> {
+    sequences
+        .into_iter()
+        .map(|sequence| NonZeroU64::new(sequence).unwrap())
+        .collect()
+}
+
+fn receipt_path(sequence: u64) -> Path {
+    Path::Receipt(ReceiptPath {
+        port_id: "transfer".parse::<PortId>().unwrap(),
+        channel_id: "channel-0".parse::<ChannelId>().unwrap(),
+        sequence: NonZeroU64::new(sequence).unwrap(),
+    })
+}
+
+fn commitment_path(sequence: u64) -> Path {
+    Path::Commitment(CommitmentPath {
+        port_id: "transfer".parse::<PortId>().unwrap(),
+        channel_id: "channel-0".parse::<ChannelId>().unwrap(),
+        sequence: NonZeroU64::new(sequence).unwrap(),
+    })
+}
+
+#[tokio::test]
+async fn unreceived_packets_are_batched_through_multicall() {
+    let execution = ibc_store_stand_in(vec![receipt_path(2), receipt_path(4)], true).await;
+
+    let mut module = module(&execution, None, HeightMode::ExecutionBlock).await;
+    module.multicall = Multicall::new(&MulticallConfig::default(), module.eth_provider.clone());
+
+    let unreceived = module
+        .query_unreceived_packets(
+            Height::new(LATEST_EXECUTION_HEIGHT),
+            "transfer".parse::<PortId>().unwrap(),
+            "channel-0".parse::<ChannelId>().unwrap(),
+            sequences(1..=4),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(unreceived, sequences([1, 3]));
+    assert_eq!(
+        calls_to(&execution, MULTICALL3_ADDRESS),
+        vec![LATEST_EXECUTION_HEIGHT]
+    );
+    assert!(calls_to(&execution, IBC_STORE_ADDRESS).is_empty());
+}
+
+#[tokio::test]
+async fn unreceived_packets_fall_back_to_individual_calls_without_multicall() {
+    let execution = ibc_store_stand_in(vec![receipt_path(2), receipt_path(4)], false).await;
+
+    // batching is enabled, but `Multicall3` is not deployed
+    let mut module = module(&execution, None, HeightMode::ExecutionBlock).await;
+    module.multicall = Multicall::new(&MulticallConfig::default(), module.eth_provider.clone());
+
+    let unreceived = module
+        .query_unreceived_packets(
+            Height::new(LATEST_EXECUTION_HEIGHT),
+            "transfer".parse::<PortId>().unwrap(),
+            "channel-0".parse::<ChannelId>().unwrap(),
+            sequences(1..=4),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(unreceived, sequences([1, 3]));
+    assert!(calls_to(&execution, MULTICALL3_ADDRESS).is_empty());
+    assert_eq!(
+        calls_to(&execution, IBC_STORE_ADDRESS),
+        vec![LATEST_EXECUTION_HEIGHT; 4]
+    );
+}
+
+#[tokio::test]
+async fn unreceived_acknowledgements_are_the_packets_still_committed() {
+    let execution = ibc_store_stand_in(vec![commitment_path(1), commitment_path(3)], true).await;
+
+    let mut module = module(&execution, None, HeightMode::ExecutionBlock).await;
+    module.multicall = Multicall::new(&MulticallConfig::default(), module.eth_provider.clone());
+
+    let unreceived = module
+        .query_unreceived_acknowledgements(
+            Height::new(LATEST_EXECUTION_HEIGHT),
+            "transfer".parse::<PortId>().unwrap(),
+            "channel-0".parse::<ChannelId>().unwrap(),
+            sequences(1..=4),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(unreceived, sequences([1, 3]));
+
+    // without batching
+    let module = self::module(&execution, None, HeightMode::ExecutionBlock).await;
+
+    let unreceived = module
+        .query_unreceived_acknowledgements(
+            Height::new(LATEST_EXECUTION_HEIGHT),
+            "transfer".parse::<PortId>().unwrap(),
+            "channel-0".parse::<ChannelId>().unwrap(),
+            sequences(1..=4),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(unreceived, sequences([1, 3]));
+    assert_eq!(
+        calls_to(&execution, IBC_STORE_ADDRESS),
+        vec![LATEST_EXECUTION_HEIGHT; 4]
+    );
+}
+
+#[test]
+fn query_server_is_served_by_default_if_the_indexer_is_enabled() {
+    let config = |extra: Value| {
+        let mut config = json!({
+            "ics26_router_address": ROUTER_ADDRESS,
+            "eth_rpc_api": "http://localhost:8545",
+        });
+        config
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+
+        serde_json::from_value::<Config>(config).unwrap()
+    };
+
+    assert_eq!(config(json!({})).query_server(), None);
+    assert_eq!(
+        config(json!({ "indexer": { "db_path": "indexer.redb" } }))
+            .query_server()
+            .unwrap()
+            .listen_address,
+        "127.0.0.1:7881".parse().unwrap()
+    );
+    assert_eq!(
+        config(json!({
+            "indexer": { "db_path": "indexer.redb" },
+            "query_server": { "listen_address": "0.0.0.0:9000" },
+        }))
+        .query_server()
+        .unwrap()
+        .listen_address,
+        "0.0.0.0:9000".parse().unwrap()
+    );
+}
+
+/// A stand-in execution RPC at [`LATEST_EXECUTION_HEIGHT`] serving `eth_getProof` from
+/// `oldest_available_height` on, and failing with `error` before that.
+async fn pruned_stand_in(oldest_available_height: u64, error: JsonRpcError) -> StandIn {
+    StandIn::json_rpc(move |method, params| match method {
+        "eth_blockNumber" => Ok(json!(format!("{LATEST_EXECUTION_HEIGHT:#x}"))),
+        "eth_getProof" if block_number(&params[2]) >= oldest_available_height => Ok(json!({
+            "address": params[0],
+            "balance": "0x0",
+            "codeHash": B256::ZERO,
+            "nonce": "0x0",
+            "storageHash": B256::ZERO,
+            "accountProof": [],
+            "storageProof": [],
+        })),
+        "eth_getProof" => Err(error.clone()),
+        _ => Err(method_not_found(method)),
+    })
+    .await
+}
+
+fn missing_trie_node() -> JsonRpcError {
+    (
+        -32_000,
+        "missing trie node 0xabcd (path ) state is not available".to_string(),
+    )
+}
+
+#[tokio::test]
+async fn probe_finds_the_state_window_of_pruned_nodes() {
+    let execution = pruned_stand_in(LATEST_EXECUTION_HEIGHT - 10, missing_trie_node()).await;
+
+    let capabilities = capabilities::probe(&eth_provider(&execution).await, ROUTER_ADDRESS)
+        .await
+        .unwrap();
+
+    assert_eq!(capabilities.state_window, Some(10));
+}
+
+#[tokio::test]
+async fn probe_treats_nodes_serving_the_first_block_as_archival() {
+    let execution = pruned_stand_in(1, missing_trie_node()).await;
+
+    let capabilities = capabilities::probe(&eth_provider(&execution).await, ROUTER_ADDRESS)
+        .await
+        .unwrap();
+
+    assert!(capabilities.is_archival());
+}
+
+#[tokio::test]
+async fn probe_fails_if_the_latest_state_is_not_available() {
+    let execution = pruned_stand_in(LATEST_EXECUTION_HEIGHT + 1, missing_trie_node()).await;
+
+    let err = capabilities::probe(&eth_provider(&execution).await, ROUTER_ADDRESS)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        ChainModuleError::PrunedState {
+            execution_height: LATEST_EXECUTION_HEIGHT,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn probe_fails_on_errors_other_than_missing_state() {
+    let execution = pruned_stand_in(
+        LATEST_EXECUTION_HEIGHT - 10,
+        (-32_005, "request rate exceeded".to_string()),
+    )
+    .await;
+
+    let err = capabilities::probe(&eth_provider(&execution).await, ROUTER_ADDRESS)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, ChainModuleError::Rpc(_)));
+}
+
+#[tokio::test]
+async fn state_availability_is_checked_against_the_cached_latest_height() {
+    let execution = execution_stand_in(vec![], |_, _, _| unreachable!()).await;
+
+    let mut module = module(&execution, None, HeightMode::ExecutionBlock).await;
+    module.node_capabilities = NodeCapabilities {
+        state_window: Some(10),
+    };
+
+    module
+        .ensure_state_available(LATEST_EXECUTION_HEIGHT)
+        .await
+        .unwrap();
+    module
+        .ensure_state_available(LATEST_EXECUTION_HEIGHT - 10)
+        .await
+        .unwrap();
+    // heights past the cached latest height are available
+    module
+        .ensure_state_available(LATEST_EXECUTION_HEIGHT + 5)
+        .await
+        .unwrap();
+
+    let err = module
+        .ensure_state_available(LATEST_EXECUTION_HEIGHT - 11)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err,
+        ChainModuleError::StateUnavailable {
+            execution_height,
+            oldest_available_height,
+        } if execution_height == LATEST_EXECUTION_HEIGHT - 11
+            && oldest_available_height == LATEST_EXECUTION_HEIGHT - 10
+    ));
+    assert_eq!(execution.json_rpc_calls("eth_blockNumber").len(), 1);
+}
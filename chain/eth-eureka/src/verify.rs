@@ -0,0 +1,101 @@
+//! Local verification of the proofs returned by `eth_getProof`
+
+use alloy::primitives::Address;
+use evm_storage_verifier::{
+    verify_account_storage_root, verify_storage_absence, verify_storage_proof,
+};
+use unionlabs::{
+    hash::{H160, H256},
+    uint::U256,
+    ErrorReporter,
+};
+
+use crate::{
+    error::ChainModuleError,
+    proof::{commitment_location, BatchedStorageProof, PathStorageProof},
+};
+
+/// Verify a batched proof of `contract` against the execution `state_root`.
+///
+/// This checks that the account proof links the contract's storage root to `state_root`, and
+/// that every storage proof is for the commitment key of its path and proves its value (or its
+/// absence) against that storage root.
+/// # Errors
+/// Returns an error if any of the proofs is invalid.
+pub fn verify_batched_proof(
+    state_root: H256,
+    contract: Address,
+    batch: &BatchedStorageProof,
+) -> Result<(), ChainModuleError> {
+    verify_account_storage_root(
+        state_root,
+        &H160::from(contract.0 .0),
+        &batch.account_proof,
+        &batch.storage_hash,
+    )
+    .map_err(|err| {
+        ChainModuleError::InvalidProof(format!(
+            "account proof of {contract} does not match state root {state_root}: {}",
+            ErrorReporter(err)
+        ))
+    })?;
+
+    batch
+        .proofs
+        .iter()
+        .try_for_each(|path_proof| verify_path_proof(batch.storage_hash, path_proof))
+}
+
+fn verify_path_proof(
+    storage_root: H256,
+    PathStorageProof { path, proof }: &PathStorageProof,
+) -> Result<(), ChainModuleError> {
+    let expected_key = commitment_location(path);
+    if proof.key != expected_key {
+        return Err(ChainModuleError::InvalidProof(format!(
+            "proof for `{path}` is for key {}, but expected {expected_key}",
+            proof.key
+        )));
+    }
+
+    let verified = if proof.value == U256::from(0_u64) {
+        verify_storage_absence(storage_root, proof.key, &proof.proof)
+    } else {
+        verify_storage_proof(
+            storage_root,
+            proof.key,
+            &rlp_encode_value(proof.value),
+            &proof.proof,
+        )
+        .map(|()| true)
+    };
+
+    match verified {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ChainModuleError::InvalidProof(format!(
+            "storage proof does not prove the absence of `{path}`"
+        ))),
+        Err(err) => Err(ChainModuleError::InvalidProof(format!(
+            "storage proof of `{path}` is invalid: {}",
+            ErrorReporter(err)
+        ))),
+    }
+}
+
+/// RLP-encode a storage value, as stored in the leaves of the storage trie.
+fn rlp_encode_value(value: U256) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let trimmed = &bytes[bytes.iter().take_while(|byte| **byte == 0).count()..];
+
+    match trimmed {
+        [byte] if *byte < 0x80 => vec![*byte],
+        _ => {
+            let mut encoded = Vec::with_capacity(trimmed.len() + 1);
+            // the value is at most 32 bytes long, so the length always fits in the prefix
+            #[allow(clippy::cast_possible_truncation)]
+            encoded.push(0x80 + trimmed.len() as u8);
+            encoded.extend_from_slice(trimmed);
+            encoded
+        }
+    }
+}
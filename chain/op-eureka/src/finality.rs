@@ -0,0 +1,291 @@
+//! Finality of OP-stack L2 blocks
+
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    primitives::{Address, U256},
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::types::BlockTransactionsKind,
+    transports::{BoxTransport, TransportError},
+};
+use ibc_eureka_rpc::{
+    config::{Endpoints, FailoverConfig},
+    execution::FailoverTransport,
+    retry::{RateLimitConfig, RetryConfig},
+};
+use serde::{Deserialize, Serialize};
+use voyager_chain_module_eth_eureka::{error::ChainModuleError, Module as EthModule};
+
+/// The name of the L1 execution RPC in the health report
+pub const L1_RPC_DEPENDENCY: &str = "l1_rpc";
+
+/// The number of latest dispute games searched for one that is finalized
+const DISPUTE_GAME_SEARCH_DEPTH: u64 = 16;
+
+/// The `GameStatus` of a dispute game whose root claim was upheld
+const DEFENDER_WINS: u8 = 2;
+
+alloy::sol! {
+    #[sol(rpc)]
+    #[allow(missing_docs, clippy::pedantic)]
+    interface L2OutputOracle {
+        function latestBlockNumber() external view returns (uint256);
+    }
+
+    #[sol(rpc)]
+    #[allow(missing_docs, clippy::pedantic)]
+    interface DisputeGameFactory {
+        struct GameSearchResult {
+            uint256 index;
+            bytes32 metadata;
+            uint64 timestamp;
+            bytes32 rootClaim;
+            bytes extraData;
+        }
+
+        function gameCount() external view returns (uint256);
+
+        function findLatestGames(uint32 gameType, uint256 start, uint256 n)
+            external
+            view
+            returns (GameSearchResult[] memory);
+    }
+
+    #[sol(rpc)]
+    #[allow(missing_docs, clippy::pedantic)]
+    interface DisputeGame {
+        function status() external view returns (uint8);
+
+        function resolvedAt() external view returns (uint64);
+    }
+
+    #[sol(rpc)]
+    #[allow(missing_docs, clippy::pedantic)]
+    interface OptimismPortal {
+        function disputeGameFinalityDelaySeconds() external view returns (uint256);
+    }
+}
+
+/// The L2 output oracle contract instance on L1
+pub type L2OutputOracleInstance =
+    L2OutputOracle::L2OutputOracleInstance<BoxTransport, RootProvider<BoxTransport>>;
+
+/// The dispute game factory contract instance on L1
+pub type DisputeGameFactoryInstance =
+    DisputeGameFactory::DisputeGameFactoryInstance<BoxTransport, RootProvider<BoxTransport>>;
+
+/// The optimism portal contract instance on L1
+pub type OptimismPortalInstance =
+    OptimismPortal::OptimismPortalInstance<BoxTransport, RootProvider<BoxTransport>>;
+
+/// How finalized L2 heights are derived
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum L2FinalityConfig {
+    /// Use the `finalized` block tag of the L2 node, which follows the L1 finality of the
+    /// batches the L2 blocks were derived from
+    #[default]
+    FinalizedTag,
+    /// Use the latest L2 block with an output root proposed to the `L2OutputOracle` in a
+    /// finalized L1 block, for chains without fault proofs
+    OutputRoot {
+        /// The RPC endpoint(s) for the L1 execution chain, in order of preference.
+        l1_rpc_api: Endpoints,
+        /// The expected chain ID of the L1 execution chain.
+        l1_chain_id: u64,
+        /// The address of the `L2OutputOracle` contract on L1.
+        l2_output_oracle_address: String,
+    },
+    /// Use the latest L2 block with a dispute game that was resolved in favor of its proposer
+    /// and whose finality delay has passed, as of the finalized L1 block, for chains with fault
+    /// proofs
+    DisputeGame {
+        /// The RPC endpoint(s) for the L1 execution chain, in order of preference.
+        l1_rpc_api: Endpoints,
+        /// The expected chain ID of the L1 execution chain.
+        l1_chain_id: u64,
+        /// The address of the `DisputeGameFactory` contract on L1.
+        dispute_game_factory_address: String,
+        /// The address of the `OptimismPortal` contract on L1, whose dispute game finality delay
+        /// must pass after a game is resolved.
+        optimism_portal_address: String,
+        /// The type of the dispute games proposing outputs, i.e. the game type respected by the
+        /// `OptimismPortal`. `0` is the permissionless and `1` the permissioned cannon game.
+        #[serde(default)]
+        game_type: u32,
+    },
+}
+
+/// The source of finalized L2 heights
+#[derive(Debug, Clone)]
+pub enum L2Finality {
+    /// The `finalized` block tag of the L2 node
+    FinalizedTag,
+    /// The latest L2 block proposed to the `L2OutputOracle`, as of the finalized L1 block
+    OutputRoot(L2OutputOracleInstance),
+    /// The latest L2 block of a finalized dispute game of `game_type`, as of the finalized L1
+    /// block
+    DisputeGame {
+        /// The dispute game factory contract on L1
+        factory: DisputeGameFactoryInstance,
+        /// The optimism portal contract on L1
+        portal: OptimismPortalInstance,
+        /// The type of the dispute games proposing outputs
+        game_type: u32,
+    },
+}
+
+impl L2Finality {
+    /// Query the latest finalized L2 block number.
+    /// # Errors
+    /// Returns an error if the rpc or contract calls fail.
+    pub async fn latest_finalized_block(
+        &self,
+        eth_module: &EthModule,
+    ) -> Result<u64, ChainModuleError> {
+        match self {
+            Self::FinalizedTag => Ok(eth_module
+                .execution_block(BlockNumberOrTag::Finalized)
+                .await?
+                .header
+                .number),
+            Self::OutputRoot(l2_output_oracle) => latest_output_block(l2_output_oracle).await,
+            Self::DisputeGame {
+                factory,
+                portal,
+                game_type,
+            } => latest_dispute_game_block(factory, portal, *game_type).await,
+        }
+    }
+}
+
+/// Connect to the L1 execution RPC endpoints, failing over, retrying and rate limiting requests
+/// like for the L2 endpoints.
+/// # Errors
+/// Returns an error if any of the endpoints cannot be connected to.
+pub async fn connect_l1(
+    l1_rpc_api: &Endpoints,
+    failover: FailoverConfig,
+    retry: RetryConfig,
+    rate_limit: Option<&RateLimitConfig>,
+) -> Result<RootProvider<BoxTransport>, TransportError> {
+    Ok(ProviderBuilder::new().on_client(
+        FailoverTransport::connect(l1_rpc_api, failover, None)
+            .await?
+            .with_retry(retry)
+            .with_rate_limit(rate_limit)
+            .with_dependency(L1_RPC_DEPENDENCY)
+            .into_client(),
+    ))
+}
+
+/// Query the latest L2 block proposed to the `L2OutputOracle`, as of the finalized L1 block.
+/// # Errors
+/// Returns an error if the contract call fails.
+pub async fn latest_output_block(
+    l2_output_oracle: &L2OutputOracleInstance,
+) -> Result<u64, ChainModuleError> {
+    let latest_block_number = l2_output_oracle
+        .latestBlockNumber()
+        .block(BlockId::finalized())
+        .call()
+        .await?
+        ._0;
+
+    to_block_number(latest_block_number)
+}
+
+/// Query the L2 block of the latest finalized dispute game of `game_type`, as of the finalized
+/// L1 block. A game is finalized once it was resolved in favor of its proposer, and the dispute
+/// game finality delay of the portal has passed since.
+/// # Errors
+/// Returns an error if the rpc or contract calls fail, or if none of the latest games is
+/// finalized.
+pub async fn latest_dispute_game_block(
+    factory: &DisputeGameFactoryInstance,
+    portal: &OptimismPortalInstance,
+    game_type: u32,
+) -> Result<u64, ChainModuleError> {
+    // all reads are pinned to the same finalized l1 block
+    let l1_block = factory
+        .provider()
+        .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+        .await?
+        .ok_or_else(|| {
+            ChainModuleError::NoFinalizedHeight("the l1 has no finalized block".to_string())
+        })?
+        .header;
+    let at = BlockId::number(l1_block.number);
+
+    let finality_delay = portal
+        .disputeGameFinalityDelaySeconds()
+        .block(at)
+        .call()
+        .await?
+        ._0;
+
+    let game_count = factory.gameCount().block(at).call().await?._0;
+
+    let games = match game_count.checked_sub(U256::from(1)) {
+        Some(latest_index) => {
+            factory
+                .findLatestGames(
+                    game_type,
+                    latest_index,
+                    U256::from(DISPUTE_GAME_SEARCH_DEPTH),
+                )
+                .block(at)
+                .call()
+                .await?
+                ._0
+        }
+        None => vec![],
+    };
+
+    // the games are ordered from the latest to the oldest
+    for game in games {
+        // the metadata packs the game type, the creation timestamp and the game address
+        let game_address = Address::from_slice(&game.metadata[12..]);
+        let dispute_game = DisputeGame::new(game_address, factory.provider().clone());
+
+        // games in progress can still be lost by their proposer
+        let status = dispute_game.status().block(at).call().await?._0;
+        if status != DEFENDER_WINS {
+            continue;
+        }
+
+        // resolved games can still be blacklisted by the guardian until the delay has passed
+        let resolved_at = dispute_game.resolvedAt().block(at).call().await?._0;
+        if U256::from(resolved_at).saturating_add(finality_delay) > U256::from(l1_block.timestamp) {
+            continue;
+        }
+
+        // the extra data of output games starts with the L2 block number of the root claim
+        let l2_block_number = game
+            .extraData
+            .get(..32)
+            .map(U256::from_be_slice)
+            .ok_or_else(|| {
+                ChainModuleError::MalformedResponse(format!(
+                    "extra data of dispute game {game_address} is too short for an l2 block \
+                    number: {}",
+                    game.extraData
+                ))
+            })?;
+
+        return to_block_number(l2_block_number);
+    }
+
+    Err(ChainModuleError::NoFinalizedHeight(format!(
+        "none of the latest {DISPUTE_GAME_SEARCH_DEPTH} dispute games of type {game_type} is \
+        finalized as of l1 block {}",
+        l1_block.number
+    )))
+}
+
+fn to_block_number(block_number: U256) -> Result<u64, ChainModuleError> {
+    block_number.try_into().map_err(|_| {
+        ChainModuleError::MalformedResponse(format!(
+            "l2 block number {block_number} does not fit in a u64"
+        ))
+    })
+}
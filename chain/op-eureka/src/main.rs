@@ -0,0 +1,429 @@
+//! OP-stack L2 Chain Module for IBC Eureka
+
+#![deny(clippy::nursery, clippy::pedantic, warnings, missing_docs)]
+
+use std::num::NonZeroU64;
+
+use alloy::{
+    primitives::{address, Address},
+    providers::{Provider, RootProvider},
+    transports::BoxTransport,
+};
+use finality::{
+    connect_l1, DisputeGameFactory, L2Finality, L2FinalityConfig, L2OutputOracle, OptimismPortal,
+    L1_RPC_DEPENDENCY,
+};
+use ibc_eureka_rpc::{
+    config::{Endpoints, FailoverConfig},
+    retry::{RateLimitConfig, RetryConfig},
+};
+use ibc_eureka_telemetry::{health::health, metrics::metrics, server::TelemetryConfig};
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    Extensions,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use unionlabs::{
+    bytes::Bytes,
+    hash::H256,
+    ibc::core::{
+        channel::channel::Channel, client::height::Height,
+        connection::connection_end::ConnectionEnd,
+    },
+    ics24::Path,
+    id::{ChannelId, ClientId, ConnectionId, PortId},
+};
+use voyager_chain_module_eth_eureka::{
+    error::{set_metrics_module, ChainModuleError},
+    height::{timestamp_nanos, Finality, HeightMode},
+    Config as EthConfig, Module as EthModule,
+};
+use voyager_message::{
+    core::ClientInfo,
+    module::{ChainModuleInfo, ChainModuleServer, RawClientState},
+    run_chain_module_server, ChainModule,
+};
+use voyager_vm::BoxDynError;
+
+pub mod finality;
+
+#[cfg(test)]
+mod tests;
+
+/// The module that metrics are recorded under
+const METRICS_MODULE: &str = "op-eureka";
+
+/// The `L1Block` predeploy, which exists on every OP-stack chain
+const L1_BLOCK_PREDEPLOY: Address = address!("4200000000000000000000000000000000000015");
+
+#[tokio::main(flavor = "multi_thread")]
+async fn main() {
+    run_chain_module_server::<Module>().await;
+}
+
+/// The OP-stack L2 Eureka Chain Module
+#[derive(Debug, Clone)]
+pub struct Module {
+    /// The Ethereum Eureka chain module serving the state queries against the L2
+    pub eth_module: EthModule,
+
+    /// The source of finalized L2 heights
+    pub finality: L2Finality,
+}
+
+/// The configuration for the OP-stack L2 Eureka Chain Module
+///
+/// The configuration of the Ethereum Eureka chain module serving the queries is read from the
+/// same object, with `eth_rpc_api` the RPC endpoint(s) of the L2. L2 heights are always
+/// execution block numbers whose finality is set by `l2_finality`, so its beacon API, height
+/// mode and finality do not apply.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// The configuration of the Ethereum Eureka chain module serving the queries against the
+    /// L2.
+    #[serde(flatten)]
+    pub eth: EthConfig,
+
+    /// How finalized L2 heights are derived.
+    #[serde(default)]
+    pub l2_finality: L2FinalityConfig,
+
+    /// Whether to skip checking that the L2 is an OP-stack chain, i.e. that the `L1Block`
+    /// predeploy exists. Useful when testing against a local stand-in RPC.
+    #[serde(default)]
+    pub skip_op_stack_check: bool,
+}
+
+impl ChainModule for Module {
+    type Config = Config;
+
+    async fn new(config: Self::Config, info: ChainModuleInfo) -> Result<Self, BoxDynError> {
+        set_metrics_module(METRICS_MODULE);
+
+        if config.eth.eth_beacon_rpc_api.is_some() || config.eth.finality.is_some() {
+            return Err(
+                "`eth_beacon_rpc_api` and `finality` are not supported for L2s, \
+                use `l2_finality` instead"
+                    .into(),
+            );
+        }
+
+        let l1_rpc = L1RpcConfig {
+            failover: config.eth.rpc_failover.clone(),
+            retry: config.eth.rpc_retry.clone(),
+            rate_limit: config.eth.rpc_rate_limit.clone(),
+        };
+
+        // NOTE: L2 heights are always execution block numbers, there is no beacon chain
+        let eth_config = EthConfig {
+            height_mode: HeightMode::ExecutionBlock,
+            finality: Some(Finality::Finalized),
+            ..config.eth
+        };
+
+        // checks that the L2 chain ID matches the chain ID voyager expects
+        let eth_module = EthModule::new(eth_config, info).await?;
+
+        if !config.skip_op_stack_check {
+            let code = eth_module
+                .eth_provider
+                .get_code_at(L1_BLOCK_PREDEPLOY)
+                .await?;
+            if code.is_empty() {
+                return Err(format!(
+                    "chain `{}` is not an OP-stack chain: the `L1Block` predeploy at \
+                    {L1_BLOCK_PREDEPLOY} has no code",
+                    eth_module.chain_id
+                )
+                .into());
+            }
+        }
+
+        let finality = match config.l2_finality {
+            L2FinalityConfig::FinalizedTag => L2Finality::FinalizedTag,
+            L2FinalityConfig::OutputRoot {
+                l1_rpc_api,
+                l1_chain_id,
+                l2_output_oracle_address,
+            } => L2Finality::OutputRoot(L2OutputOracle::new(
+                l2_output_oracle_address.parse()?,
+                l1_rpc.connect(&l1_rpc_api, l1_chain_id).await?,
+            )),
+            L2FinalityConfig::DisputeGame {
+                l1_rpc_api,
+                l1_chain_id,
+                dispute_game_factory_address,
+                optimism_portal_address,
+                game_type,
+            } => {
+                let l1_provider = l1_rpc.connect(&l1_rpc_api, l1_chain_id).await?;
+
+                L2Finality::DisputeGame {
+                    factory: DisputeGameFactory::new(
+                        dispute_game_factory_address.parse()?,
+                        l1_provider.clone(),
+                    ),
+                    portal: OptimismPortal::new(optimism_portal_address.parse()?, l1_provider),
+                    game_type,
+                }
+            }
+        };
+
+        Ok(Self {
+            eth_module,
+            finality,
+        })
+    }
+}
+
+/// The failover, retry and rate limiting of the L1 endpoints, shared with the L2 endpoints
+struct L1RpcConfig {
+    failover: FailoverConfig,
+    retry: RetryConfig,
+    rate_limit: Option<RateLimitConfig>,
+}
+
+impl L1RpcConfig {
+    /// Connect to the L1 execution chain, register its health check and check its chain ID.
+    async fn connect(
+        &self,
+        l1_rpc_api: &Endpoints,
+        l1_chain_id: u64,
+    ) -> Result<RootProvider<BoxTransport>, BoxDynError> {
+        let l1_provider = connect_l1(
+            l1_rpc_api,
+            self.failover.clone(),
+            self.retry.clone(),
+            self.rate_limit.as_ref(),
+        )
+        .await?;
+
+        let l1_check_provider = l1_provider.clone();
+        health().register_check(L1_RPC_DEPENDENCY, move || {
+            let l1_provider = l1_check_provider.clone();
+            async move {
+                l1_provider
+                    .get_block_number()
+                    .await
+                    .map(Some)
+                    .map_err(|err| err.to_string())
+            }
+        });
+
+        let found_l1_chain_id = l1_provider.get_chain_id().await?;
+        if found_l1_chain_id != l1_chain_id {
+            return Err(format!(
+                "incorrect l1 chain id: expected `{l1_chain_id}`, but found `{found_l1_chain_id}`"
+            )
+            .into());
+        }
+
+        Ok(l1_provider)
+    }
+}
+
+impl Module {
+    /// Query the latest finalized L2 block number and its timestamp, in nanoseconds.
+    /// # Errors
+    /// Returns an error if the rpc or contract calls fail, or if the timestamp overflows.
+    pub async fn query_latest_finalized(&self) -> Result<(u64, u64), ChainModuleError> {
+        let block_number = self
+            .finality
+            .latest_finalized_block(&self.eth_module)
+            .await?;
+
+        let timestamp = self
+            .eth_module
+            .execution_block(block_number.into())
+            .await?
+            .header
+            .timestamp;
+
+        Ok((block_number, timestamp_nanos(timestamp)?))
+    }
+}
+
+#[async_trait]
+impl ChainModuleServer for Module {
+    /// Query the latest finalized height of the L2.
+    async fn query_latest_height(&self, _: &Extensions) -> RpcResult<Height> {
+        let (block_number, _) = self.query_latest_finalized().await?;
+        metrics().record_latest_height(
+            METRICS_MODULE,
+            self.eth_module.chain_id.as_str(),
+            block_number,
+        );
+        health().record_latest_height(self.eth_module.chain_id.as_str(), block_number);
+
+        Ok(self.eth_module.make_height(block_number))
+    }
+
+    /// Query the latest finalized timestamp of the L2, in nanoseconds.
+    async fn query_latest_timestamp(&self, _: &Extensions) -> RpcResult<i64> {
+        let (_, timestamp) = self.query_latest_finalized().await?;
+
+        Ok(timestamp.try_into().map_err(|_| {
+            ChainModuleError::MalformedResponse(format!(
+                "timestamp {timestamp} does not fit in an i64"
+            ))
+        })?)
+    }
+
+    async fn query_client_prefix(&self, e: &Extensions, raw_client_id: u32) -> RpcResult<String> {
+        self.eth_module.query_client_prefix(e, raw_client_id).await
+    }
+
+    async fn client_info(&self, e: &Extensions, client_id: ClientId) -> RpcResult<ClientInfo> {
+        self.eth_module.client_info(e, client_id).await
+    }
+
+    async fn query_client_state(
+        &self,
+        e: &Extensions,
+        height: Height,
+        client_id: ClientId,
+    ) -> RpcResult<Bytes> {
+        self.eth_module
+            .query_client_state(e, height, client_id)
+            .await
+    }
+
+    async fn query_commitment(
+        &self,
+        e: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequence: NonZeroU64,
+    ) -> RpcResult<Option<H256>> {
+        self.eth_module
+            .query_commitment(e, height, port_id, channel_id, sequence)
+            .await
+    }
+
+    async fn query_acknowledgement(
+        &self,
+        e: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequence: NonZeroU64,
+    ) -> RpcResult<Option<H256>> {
+        self.eth_module
+            .query_acknowledgement(e, height, port_id, channel_id, sequence)
+            .await
+    }
+
+    async fn query_receipt(
+        &self,
+        e: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+        sequence: NonZeroU64,
+    ) -> RpcResult<bool> {
+        self.eth_module
+            .query_receipt(e, height, port_id, channel_id, sequence)
+            .await
+    }
+
+    async fn query_ibc_proof(&self, e: &Extensions, at: Height, path: Path) -> RpcResult<Value> {
+        self.eth_module.query_ibc_proof(e, at, path).await
+    }
+
+    async fn query_raw_unfinalized_trusted_client_state(
+        &self,
+        e: &Extensions,
+        client_id: ClientId,
+    ) -> RpcResult<RawClientState> {
+        self.eth_module
+            .query_raw_unfinalized_trusted_client_state(e, client_id)
+            .await
+    }
+
+    async fn query_client_consensus_state(
+        &self,
+        e: &Extensions,
+        height: Height,
+        client_id: ClientId,
+        trusted_height: Height,
+    ) -> RpcResult<Bytes> {
+        self.eth_module
+            .query_client_consensus_state(e, height, client_id, trusted_height)
+            .await
+    }
+
+    async fn query_connection(
+        &self,
+        e: &Extensions,
+        height: Height,
+        connection_id: ConnectionId,
+    ) -> RpcResult<Option<ConnectionEnd>> {
+        self.eth_module
+            .query_connection(e, height, connection_id)
+            .await
+    }
+
+    async fn query_channel(
+        &self,
+        e: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+    ) -> RpcResult<Option<Channel>> {
+        self.eth_module
+            .query_channel(e, height, port_id, channel_id)
+            .await
+    }
+
+    async fn query_next_sequence_send(
+        &self,
+        e: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+    ) -> RpcResult<u64> {
+        self.eth_module
+            .query_next_sequence_send(e, height, port_id, channel_id)
+            .await
+    }
+
+    async fn query_next_sequence_recv(
+        &self,
+        e: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+    ) -> RpcResult<u64> {
+        self.eth_module
+            .query_next_sequence_recv(e, height, port_id, channel_id)
+            .await
+    }
+
+    async fn query_next_sequence_ack(
+        &self,
+        e: &Extensions,
+        height: Height,
+        port_id: PortId,
+        channel_id: ChannelId,
+    ) -> RpcResult<u64> {
+        self.eth_module
+            .query_next_sequence_ack(e, height, port_id, channel_id)
+            .await
+    }
+
+    async fn query_next_connection_sequence(
+        &self,
+        e: &Extensions,
+        height: Height,
+    ) -> RpcResult<u64> {
+        self.eth_module
+            .query_next_connection_sequence(e, height)
+            .await
+    }
+
+    async fn query_next_client_sequence(&self, e: &Extensions, height: Height) -> RpcResult<u64> {
+        self.eth_module.query_next_client_sequence(e, height).await
+    }
+}
@@ -0,0 +1,386 @@
+//! Tests of the L2 finality against stand-in L1 endpoints
+
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use alloy::{
+    primitives::{address, Address, Bytes, B256, U256},
+    providers::{Provider, RootProvider},
+    sol_types::SolCall,
+    transports::BoxTransport,
+};
+use ibc_eureka_rpc::config::{Endpoints, FailoverConfig};
+use ibc_eureka_test_utils::{
+    eth::{block_number, eth_call_input, fast_retry, method_not_found, selector},
+    stand_in::StandIn,
+};
+use serde_json::{json, Value};
+use voyager_chain_module_eth_eureka::error::ChainModuleError;
+
+use crate::{
+    finality::{
+        connect_l1, latest_dispute_game_block, latest_output_block, DisputeGame,
+        DisputeGameFactory, L2FinalityConfig, L2OutputOracle, OptimismPortal,
+    },
+    Config,
+};
+
+const L2_OUTPUT_ORACLE_ADDRESS: Address = address!("1000000000000000000000000000000000000001");
+const DISPUTE_GAME_FACTORY_ADDRESS: Address = address!("1000000000000000000000000000000000000002");
+const OPTIMISM_PORTAL_ADDRESS: Address = address!("1000000000000000000000000000000000000003");
+const IN_PROGRESS_GAME_ADDRESS: Address = address!("2000000000000000000000000000000000000000");
+const CHALLENGED_GAME_ADDRESS: Address = address!("2000000000000000000000000000000000000001");
+const DEFENDED_GAME_ADDRESS: Address = address!("2000000000000000000000000000000000000002");
+const RECENTLY_DEFENDED_GAME_ADDRESS: Address =
+    address!("2000000000000000000000000000000000000003");
+
+/// The number and timestamp of the finalized L1 block served by [`dispute_game_stand_in`]
+const L1_FINALIZED_BLOCK: u64 = 1_000;
+const L1_FINALIZED_TIMESTAMP: u64 = 1_700_000_000;
+
+/// The dispute game finality delay of the portal, in seconds
+const FINALITY_DELAY: u64 = 3_600;
+
+/// The `GameStatus` of a dispute game that is not resolved yet
+const IN_PROGRESS: u8 = 0;
+
+/// The `GameStatus` of a dispute game whose root claim was proven wrong
+const CHALLENGER_WINS: u8 = 1;
+
+/// The `GameStatus` of a dispute game whose root claim was upheld
+const DEFENDER_WINS: u8 = 2;
+
+/// A dispute game served by [`dispute_game_stand_in`]
+#[derive(Clone, Copy)]
+struct Game {
+    address: Address,
+    status: u8,
+    resolved_at: u64,
+    l2_block_number: u64,
+}
+
+impl Game {
+    /// A game resolved long enough ago for the finality delay to have passed
+    const fn resolved(address: Address, status: u8, l2_block_number: u64) -> Self {
+        Self {
+            address,
+            status,
+            resolved_at: L1_FINALIZED_TIMESTAMP - 2 * FINALITY_DELAY,
+            l2_block_number,
+        }
+    }
+}
+
+async fn l1_provider(l1: &StandIn) -> RootProvider<BoxTransport> {
+    connect_l1(
+        &Endpoints::Single(l1.url()),
+        FailoverConfig::default(),
+        fast_retry(3),
+        None,
+    )
+    .await
+    .unwrap()
+}
+
+/// The finalized L1 block, without transactions
+fn l1_finalized_block() -> Value {
+    json!({
+        "hash": B256::repeat_byte(1),
+        "parentHash": B256::repeat_byte(2),
+        "sha3Uncles": B256::repeat_byte(3),
+        "miner": Address::ZERO,
+        "stateRoot": B256::repeat_byte(4),
+        "transactionsRoot": B256::repeat_byte(5),
+        "receiptsRoot": B256::repeat_byte(6),
+        "logsBloom": format!("0x{}", "00".repeat(256)),
+        "difficulty": "0x0",
+        "number": format!("{L1_FINALIZED_BLOCK:#x}"),
+        "gasLimit": "0x1c9c380",
+        "gasUsed": "0x0",
+        "timestamp": format!("{L1_FINALIZED_TIMESTAMP:#x}"),
+        "extraData": "0x",
+        "mixHash": B256::ZERO,
+        "nonce": "0x0000000000000000",
+        "baseFeePerGas": "0x7",
+        "totalDifficulty": "0x0",
+        "size": "0x220",
+        "uncles": [],
+        "transactions": [],
+    })
+}
+
+/// A stand-in L1 RPC with the `games` of a dispute game factory, from the latest to the oldest,
+/// out of `game_count` games. All reads are expected at the finalized L1 block.
+async fn dispute_game_stand_in(game_count: u64, games: Vec<Game>) -> StandIn {
+    StandIn::eth_json_rpc(
+        |method, params| match method {
+            "eth_getBlockByNumber" => {
+                assert_eq!(params[0], "finalized");
+
+                Ok(l1_finalized_block())
+            }
+            _ => Err(method_not_found(method)),
+        },
+        move |to, input, block| {
+            assert_eq!(block_number(block), L1_FINALIZED_BLOCK);
+
+            match (to, selector(input)) {
+                (OPTIMISM_PORTAL_ADDRESS, function) => {
+                    assert_eq!(
+                        function,
+                        OptimismPortal::disputeGameFinalityDelaySecondsCall::SELECTOR
+                    );
+
+                    Ok(
+                        OptimismPortal::disputeGameFinalityDelaySecondsCall::abi_encode_returns(&(
+                            U256::from(FINALITY_DELAY),
+                        )),
+                    )
+                }
+                (DISPUTE_GAME_FACTORY_ADDRESS, DisputeGameFactory::gameCountCall::SELECTOR) => {
+                    Ok(DisputeGameFactory::gameCountCall::abi_encode_returns(&(
+                        U256::from(game_count),
+                    )))
+                }
+                (
+                    DISPUTE_GAME_FACTORY_ADDRESS,
+                    DisputeGameFactory::findLatestGamesCall::SELECTOR,
+                ) => {
+                    let call =
+                        DisputeGameFactory::findLatestGamesCall::abi_decode(input, true).unwrap();
+                    assert_eq!(call.gameType, 0);
+                    assert_eq!(call.start, U256::from(game_count - 1));
+
+                    let results = games
+                        .iter()
+                        .zip((0..game_count).rev())
+                        .map(|(game, index)| DisputeGameFactory::GameSearchResult {
+                            index: U256::from(index),
+                            metadata: game.address.into_word(),
+                            timestamp: 0,
+                            rootClaim: B256::repeat_byte(1),
+                            extraData: Bytes::from(
+                                U256::from(game.l2_block_number).to_be_bytes_vec(),
+                            ),
+                        })
+                        .collect::<Vec<_>>();
+
+                    Ok(DisputeGameFactory::findLatestGamesCall::abi_encode_returns(
+                        &(results,),
+                    ))
+                }
+                (DISPUTE_GAME_FACTORY_ADDRESS, _) => Err((3, "execution reverted".to_string())),
+                (game_address, function) => {
+                    let game = games
+                        .iter()
+                        .find(|game| game.address == game_address)
+                        .unwrap();
+
+                    match function {
+                        DisputeGame::statusCall::SELECTOR => {
+                            Ok(DisputeGame::statusCall::abi_encode_returns(&(game.status,)))
+                        }
+                        DisputeGame::resolvedAtCall::SELECTOR => Ok(
+                            DisputeGame::resolvedAtCall::abi_encode_returns(&(game.resolved_at,)),
+                        ),
+                        _ => Err((3, "execution reverted".to_string())),
+                    }
+                }
+            }
+        },
+    )
+    .await
+}
+
+async fn latest_finalized_game_block(l1: &StandIn) -> Result<u64, ChainModuleError> {
+    let l1_provider = l1_provider(l1).await;
+    let factory = DisputeGameFactory::new(DISPUTE_GAME_FACTORY_ADDRESS, l1_provider.clone());
+    let portal = OptimismPortal::new(OPTIMISM_PORTAL_ADDRESS, l1_provider);
+
+    latest_dispute_game_block(&factory, &portal, 0).await
+}
+
+#[tokio::test]
+async fn output_root_finality_is_the_latest_proposed_block() {
+    let l1 = StandIn::eth_json_rpc(
+        |method, _| Err(method_not_found(method)),
+        |to, input, block| {
+            assert_eq!(block, "finalized");
+            assert_eq!(to, L2_OUTPUT_ORACLE_ADDRESS);
+            assert_eq!(
+                selector(input),
+                L2OutputOracle::latestBlockNumberCall::SELECTOR
+            );
+
+            Ok(L2OutputOracle::latestBlockNumberCall::abi_encode_returns(
+                &(U256::from(1_800),),
+            ))
+        },
+    )
+    .await;
+
+    let l2_output_oracle = L2OutputOracle::new(L2_OUTPUT_ORACLE_ADDRESS, l1_provider(&l1).await);
+
+    assert_eq!(latest_output_block(&l2_output_oracle).await.unwrap(), 1_800);
+}
+
+#[tokio::test]
+async fn dispute_game_finality_skips_games_won_by_the_challenger() {
+    let l1 = dispute_game_stand_in(
+        5,
+        vec![
+            Game::resolved(CHALLENGED_GAME_ADDRESS, CHALLENGER_WINS, 2_000),
+            Game::resolved(DEFENDED_GAME_ADDRESS, DEFENDER_WINS, 1_800),
+        ],
+    )
+    .await;
+
+    assert_eq!(latest_finalized_game_block(&l1).await.unwrap(), 1_800);
+}
+
+#[tokio::test]
+async fn dispute_game_finality_skips_games_in_progress() {
+    let l1 = dispute_game_stand_in(
+        5,
+        vec![
+            Game::resolved(IN_PROGRESS_GAME_ADDRESS, IN_PROGRESS, 2_000),
+            Game::resolved(DEFENDED_GAME_ADDRESS, DEFENDER_WINS, 1_800),
+        ],
+    )
+    .await;
+
+    assert_eq!(latest_finalized_game_block(&l1).await.unwrap(), 1_800);
+
+    // only the status of the game in progress is read
+    assert!(l1
+        .json_rpc_calls("eth_call")
+        .iter()
+        .map(eth_call_input)
+        .filter(|(to, _)| *to == IN_PROGRESS_GAME_ADDRESS)
+        .all(|(_, input)| selector(&input) == DisputeGame::statusCall::SELECTOR));
+}
+
+#[tokio::test]
+async fn dispute_game_finality_waits_for_the_finality_delay() {
+    let l1 = dispute_game_stand_in(
+        5,
+        vec![
+            Game {
+                resolved_at: L1_FINALIZED_TIMESTAMP - FINALITY_DELAY + 1,
+                ..Game::resolved(RECENTLY_DEFENDED_GAME_ADDRESS, DEFENDER_WINS, 2_000)
+            },
+            Game {
+                resolved_at: L1_FINALIZED_TIMESTAMP - FINALITY_DELAY,
+                ..Game::resolved(DEFENDED_GAME_ADDRESS, DEFENDER_WINS, 1_800)
+            },
+        ],
+    )
+    .await;
+
+    assert_eq!(latest_finalized_game_block(&l1).await.unwrap(), 1_800);
+}
+
+#[tokio::test]
+async fn dispute_game_finality_requires_a_finalized_game() {
+    let l1 = dispute_game_stand_in(
+        1,
+        vec![Game::resolved(IN_PROGRESS_GAME_ADDRESS, IN_PROGRESS, 2_000)],
+    )
+    .await;
+
+    assert!(matches!(
+        latest_finalized_game_block(&l1).await.unwrap_err(),
+        ChainModuleError::NoFinalizedHeight(_)
+    ));
+
+    let l1 = dispute_game_stand_in(0, vec![]).await;
+
+    assert!(matches!(
+        latest_finalized_game_block(&l1).await.unwrap_err(),
+        ChainModuleError::NoFinalizedHeight(_)
+    ));
+}
+
+#[tokio::test]
+async fn l1_requests_are_retried() {
+    let attempts = Arc::new(AtomicUsize::new(0));
+
+    let l1 = {
+        let attempts = attempts.clone();
+        StandIn::serve(move |request| {
+            // the first attempt is rate limited
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                return (429, json!({ "message": "too many requests" }));
+            }
+
+            let body = request.body.as_ref().unwrap();
+            assert_eq!(body["method"], "eth_chainId");
+
+            (
+                200,
+                json!({ "jsonrpc": "2.0", "id": body["id"], "result": "0x1" }),
+            )
+        })
+        .await
+    };
+
+    assert_eq!(l1_provider(&l1).await.get_chain_id().await.unwrap(), 1);
+    assert_eq!(l1.requests().len(), 2);
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn finality_config_parses_every_source() {
+    for (config, expected) in [
+        (json!({ "type": "finalized_tag" }), "finalized_tag"),
+        (
+            json!({
+                "type": "output_root",
+                "l1_rpc_api": "http://localhost:8545",
+                "l1_chain_id": 1,
+                "l2_output_oracle_address": L2_OUTPUT_ORACLE_ADDRESS,
+            }),
+            "output_root",
+        ),
+        (
+            json!({
+                "type": "dispute_game",
+                "l1_rpc_api": ["http://localhost:8545", "http://localhost:8546"],
+                "l1_chain_id": 1,
+                "dispute_game_factory_address": DISPUTE_GAME_FACTORY_ADDRESS,
+                "optimism_portal_address": OPTIMISM_PORTAL_ADDRESS,
+            }),
+            "dispute_game",
+        ),
+    ] {
+        let parsed = serde_json::from_value::<L2FinalityConfig>(config).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(parsed).unwrap()["type"],
+            Value::from(expected)
+        );
+    }
+}
+
+#[test]
+fn config_embeds_the_eth_eureka_config() {
+    let config = serde_json::from_value::<Config>(json!({
+        "ics26_router_address": "0x1000000000000000000000000000000000000026",
+        "eth_rpc_api": "http://localhost:9545",
+        "rpc_retry": { "max_attempts": 5 },
+        "l2_finality": { "type": "finalized_tag" },
+        "skip_op_stack_check": true,
+    }))
+    .unwrap();
+
+    assert_eq!(
+        config.eth.eth_rpc_api,
+        Endpoints::Single("http://localhost:9545".to_string())
+    );
+    assert_eq!(config.eth.rpc_retry.max_attempts, 5);
+    assert_eq!(config.eth.finality, None);
+    assert_eq!(config.l2_finality, L2FinalityConfig::FinalizedTag);
+    assert!(config.skip_op_stack_check);
+}
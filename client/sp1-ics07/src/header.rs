@@ -0,0 +1,28 @@
+//! Headers encoded by the SP1 ICS07 Light Client Module
+
+use alloy::sol_types::SolValue;
+use sp1_ics07_tendermint_solidity::{ISP1Msgs::SP1Proof, IUpdateClientMsgs::MsgUpdateClient};
+
+/// The proof-carrying header produced by the SP1 ICS07 client update plugin. Only the proof is
+/// read from the `ProveResponse` of the plugin, as it commits to the trusted consensus state and
+/// the proposed header.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Sp1Header {
+    /// The ABI-encoded [`SP1Proof`] of the update
+    pub sp1_proof: Vec<u8>,
+}
+
+impl Sp1Header {
+    /// Encode the header as the ABI `MsgUpdateClient` expected by `updateClient` of the SP1
+    /// ICS07 Tendermint contract.
+    /// # Errors
+    /// Fails if `sp1_proof` is not an ABI-encoded [`SP1Proof`]
+    pub fn to_sol_update_msg(&self) -> Result<Vec<u8>, alloy::sol_types::Error> {
+        let sp1_proof = SP1Proof::abi_decode(&self.sp1_proof, true)?;
+
+        Ok(MsgUpdateClient {
+            sp1Proof: sp1_proof,
+        }
+        .abi_encode())
+    }
+}
@@ -2,20 +2,30 @@
 
 #![deny(clippy::nursery, clippy::pedantic, warnings, missing_docs)]
 
-use alloy::sol_types::SolValue;
+use std::collections::BTreeMap;
+
+use alloy::{primitives::B256, sol_types::SolValue};
+use header::Sp1Header;
 use ibc_client_tendermint_types::Header;
-use ibc_eureka_types::SOL_IBC_EUREKA_INTERFACE;
+use ibc_eureka_telemetry::{metrics::metrics, server::TelemetryConfig};
+use ibc_eureka_types::{
+    IBC_GO_08_WASM_INTERFACE, SOL_IBC_EUREKA_INTERFACE, SOL_IBC_EUREKA_PROTOBUF_HEADER_INTERFACE,
+};
 use ibc_eureka_union_ext::height::IntoUnionHeight;
-use ibc_proto::ibc::lightclients::tendermint::v1::Header as RawHeader;
+use ibc_proto::ibc::{
+    core::client::v1::Height as WasmHeight, lightclients::tendermint::v1::Header as RawHeader,
+};
 use jsonrpsee::{
     core::{async_trait, RpcResult},
-    types::ErrorObject,
+    types::ErrorObjectOwned,
     Extensions,
 };
+use proof::EncodableProof;
+use reencode::Reencoding;
 use serde_json::{json, Value};
 use sp1_ics07_tendermint_solidity::{
     IICS07TendermintMsgs::{ClientState, ConsensusState},
-    IMembershipMsgs::MembershipProof,
+    ISP1Msgs::SupportedZkAlgorithm,
 };
 use tendermint_proto::Protobuf;
 use unionlabs::{bytes::Bytes, ErrorReporter};
@@ -26,6 +36,17 @@ use voyager_message::{
 };
 use voyager_vm::BoxDynError;
 
+pub mod header;
+pub mod proof;
+pub mod reencode;
+pub mod wasm;
+
+#[cfg(test)]
+mod tests;
+
+/// The module name used as a metric label
+const METRICS_MODULE: &str = "client-sp1-ics07";
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
     run_client_module_server::<Module>().await;
@@ -36,10 +57,27 @@ async fn main() {
 pub enum SupportedIbcInterfaces {
     /// The Solidity IBC Eureka interface
     SolidityIbcEureka,
+    /// The Solidity IBC Eureka interface, with headers encoded as protobuf Tendermint headers
+    /// rather than as proven ABI `MsgUpdateClient`s
+    SolidityIbcEurekaProtobufHeader,
+    /// The ibc-go interface, with the light client hosted by 08-wasm. States, headers and proofs
+    /// are ABI-encoded as for the Solidity interface, and states and headers are wrapped into
+    /// their `Any`-wrapped 08-wasm types.
+    IbcGo08Wasm,
 }
 
-/// The supported zero-knowledge proof algorithms
-#[derive(Debug, Clone)]
+/// The metadata required to encode a client state for the ibc-go 08-wasm interface
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WasmClientStateMetadata {
+    /// The checksum of the stored SP1 ICS07 wasm light client code
+    pub checksum: B256,
+}
+
+/// The supported zero-knowledge proof algorithms, read from the `zkAlgorithm` of each client
+/// state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SupportedZkAlgorithms {
     /// SP1's Groth16
     Groth16,
@@ -48,29 +86,49 @@ pub enum SupportedZkAlgorithms {
 }
 
 /// The configuration for the SP1 ICS07 Light Client Module
-/// No configuration is required for this module
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct Config {}
+pub struct Config {
+    /// The telemetry endpoint, serving metrics and health checks. It is disabled if this is not
+    /// set.
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
+
+    /// The checksums of the 08-wasm code of the counterparty light clients hosted by 08-wasm,
+    /// keyed by client type. Required to re-encode the states of ethereum counterparty clients.
+    #[serde(default)]
+    pub counterparty_wasm_checksums: BTreeMap<String, B256>,
+}
 
 /// The SP1 ICS07 Light Client Module
 #[derive(Debug, Clone)]
 pub struct Module {
     /// The ibc interface used in this instance
     pub ibc_interface: SupportedIbcInterfaces,
-    /// The zero-knowledge algorithm used in this instance
-    pub zk_algorithm: SupportedZkAlgorithms,
+
+    /// The checksums of the 08-wasm code of the counterparty light clients, by client type
+    pub counterparty_wasm_checksums: BTreeMap<String, B256>,
 }
 
 impl ClientModule for Module {
     type Config = Config;
 
-    async fn new(Config {}: Self::Config, info: ClientModuleInfo) -> Result<Self, BoxDynError> {
+    async fn new(
+        Config {
+            telemetry: telemetry_config,
+            counterparty_wasm_checksums,
+        }: Self::Config,
+        info: ClientModuleInfo,
+    ) -> Result<Self, BoxDynError> {
+        if let Some(telemetry_config) = &telemetry_config {
+            ibc_eureka_telemetry::server::spawn(telemetry_config).await?;
+        }
+
         info.ensure_client_type(ibc_eureka_types::SP1_ICS07_CLIENT_TYPE)?;
         info.ensure_consensus_type(ConsensusType::TENDERMINT)?;
 
         Ok(Self {
             ibc_interface: SupportedIbcInterfaces::try_from(info.ibc_interface.to_string())?,
-            zk_algorithm: SupportedZkAlgorithms::Plonk,
+            counterparty_wasm_checksums,
         })
     }
 }
@@ -103,7 +161,15 @@ impl ClientModuleServer for Module {
     }
 
     async fn decode_client_state(&self, _: &Extensions, client_state: Bytes) -> RpcResult<Value> {
-        Ok(serde_json::to_value(self.decode_client_state(&client_state.into_vec())?).unwrap())
+        let cs = self.decode_client_state(&client_state.into_vec())?;
+
+        serde_json::to_value(cs).map_err(|err| {
+            fatal_error(
+                "invalid_client_state",
+                format!("unable to serialize client state: {}", ErrorReporter(err)),
+                None,
+            )
+        })
     }
 
     async fn decode_consensus_state(
@@ -111,10 +177,18 @@ impl ClientModuleServer for Module {
         _: &Extensions,
         consensus_state: Bytes,
     ) -> RpcResult<Value> {
-        Ok(
-            serde_json::to_value(self.decode_consensus_state(&consensus_state.into_vec())?)
-                .unwrap(),
-        )
+        let cs = self.decode_consensus_state(&consensus_state.into_vec())?;
+
+        serde_json::to_value(cs).map_err(|err| {
+            fatal_error(
+                "invalid_consensus_state",
+                format!(
+                    "unable to serialize consensus state: {}",
+                    ErrorReporter(err)
+                ),
+                None,
+            )
+        })
     }
 
     async fn encode_client_state(
@@ -123,31 +197,59 @@ impl ClientModuleServer for Module {
         client_state: Value,
         metadata: Value,
     ) -> RpcResult<Bytes> {
-        serde_json::from_value::<ClientState>(client_state)
-            .map_err(|err| {
-                ErrorObject::owned(
-                    FATAL_JSONRPC_ERROR_CODE,
-                    format!("unable to deserialize client state: {}", ErrorReporter(err)),
-                    None::<()>,
-                )
-            })
-            .and_then(|cs| match self.ibc_interface {
-                SupportedIbcInterfaces::SolidityIbcEureka => {
-                    if !metadata.is_null() {
-                        return Err(ErrorObject::owned(
-                            FATAL_JSONRPC_ERROR_CODE,
-                            "metadata was provided, but this client type does not require \
-                            metadata for client state encoding",
+        let cs = serde_json::from_value::<ClientState>(client_state).map_err(|err| {
+            fatal_error(
+                "invalid_client_state",
+                format!("unable to deserialize client state: {}", ErrorReporter(err)),
+                None,
+            )
+        })?;
+        zk_algorithm(&cs)?;
+
+        match self.ibc_interface {
+            SupportedIbcInterfaces::SolidityIbcEureka
+            | SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader => {
+                if !metadata.is_null() {
+                    return Err(fatal_error(
+                        "unexpected_metadata",
+                        "metadata was provided, but this client type does not require \
+                        metadata for client state encoding",
+                        Some(json!({
+                            "provided_metadata": metadata,
+                        })),
+                    ));
+                }
+
+                Ok(cs.abi_encode())
+            }
+            SupportedIbcInterfaces::IbcGo08Wasm => {
+                let WasmClientStateMetadata { checksum } = serde_json::from_value(metadata.clone())
+                    .map_err(|err| {
+                        fatal_error(
+                            "invalid_metadata",
+                            format!(
+                                "unable to deserialize 08-wasm client state metadata: {}",
+                                ErrorReporter(err)
+                            ),
                             Some(json!({
                                 "provided_metadata": metadata,
                             })),
-                        ));
-                    }
+                        )
+                    })?;
 
-                    Ok(cs.abi_encode())
-                }
-            })
-            .map(Bytes::from)
+                let latest_height = WasmHeight {
+                    revision_number: cs.latestHeight.revisionNumber.into(),
+                    revision_height: cs.latestHeight.revisionHeight.into(),
+                };
+
+                Ok(wasm::wrap_client_state(
+                    cs.abi_encode(),
+                    checksum.to_vec(),
+                    Some(latest_height),
+                ))
+            }
+        }
+        .map(Bytes::from)
     }
 
     async fn encode_consensus_state(
@@ -157,17 +259,19 @@ impl ClientModuleServer for Module {
     ) -> RpcResult<Bytes> {
         serde_json::from_value::<ConsensusState>(consensus_state)
             .map_err(|err| {
-                ErrorObject::owned(
-                    FATAL_JSONRPC_ERROR_CODE,
+                fatal_error(
+                    "invalid_consensus_state",
                     format!(
                         "unable to deserialize consensus state: {}",
                         ErrorReporter(err)
                     ),
-                    None::<()>,
+                    None,
                 )
             })
             .map(|cs| match self.ibc_interface {
-                SupportedIbcInterfaces::SolidityIbcEureka => cs.abi_encode(),
+                SupportedIbcInterfaces::SolidityIbcEureka
+                | SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader => cs.abi_encode(),
+                SupportedIbcInterfaces::IbcGo08Wasm => wasm::wrap_consensus_state(cs.abi_encode()),
             })
             .map(Bytes::from)
     }
@@ -176,83 +280,196 @@ impl ClientModuleServer for Module {
         &self,
         _: &Extensions,
         client_state: Bytes,
-        _client_type: ClientType<'static>,
+        client_type: ClientType<'static>,
     ) -> RpcResult<Bytes> {
-        Ok(client_state)
+        match self.counterparty_reencoding(&client_type)? {
+            Reencoding::PassThrough => Ok(client_state),
+            // NOTE: the 08-wasm of ibc-go v10, which hosts ibc eureka, does not store the
+            // latest height
+            Reencoding::WrapWasm { checksum } => Ok(Bytes::from(wasm::wrap_client_state(
+                client_state.into_vec(),
+                checksum.to_vec(),
+                None,
+            ))),
+        }
     }
 
     async fn reencode_counterparty_consensus_state(
         &self,
         _: &Extensions,
         consensus_state: Bytes,
-        _client_type: ClientType<'static>,
+        client_type: ClientType<'static>,
     ) -> RpcResult<Bytes> {
-        Ok(consensus_state)
+        match self.counterparty_reencoding(&client_type)? {
+            Reencoding::PassThrough => Ok(consensus_state),
+            Reencoding::WrapWasm { .. } => Ok(Bytes::from(wasm::wrap_consensus_state(
+                consensus_state.into_vec(),
+            ))),
+        }
     }
 
-    // NOTE: We always serialize the header using protobuf
     async fn encode_header(&self, _: &Extensions, header: Value) -> RpcResult<Bytes> {
-        serde_json::from_value::<Header>(header)
-            .map_err(|err| {
-                ErrorObject::owned(
-                    FATAL_JSONRPC_ERROR_CODE,
-                    format!("unable to deserialize header: {}", ErrorReporter(err)),
-                    None::<()>,
-                )
-            })
-            .map(<Header as Protobuf<RawHeader>>::encode_vec)
-            .map(Bytes::from)
+        if matches!(
+            self.ibc_interface,
+            SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader
+        ) {
+            return serde_json::from_value::<Header>(header)
+                .map(<Header as Protobuf<RawHeader>>::encode_vec)
+                .map(Bytes::from)
+                .map_err(|err| {
+                    fatal_error(
+                        "invalid_header",
+                        format!("unable to deserialize header: {}", ErrorReporter(err)),
+                        None,
+                    )
+                });
+        }
+
+        let header = serde_json::from_value::<Sp1Header>(header).map_err(|err| {
+            fatal_error(
+                "invalid_header",
+                format!("unable to deserialize header: {}", ErrorReporter(err)),
+                None,
+            )
+        })?;
+
+        match self.ibc_interface {
+            SupportedIbcInterfaces::SolidityIbcEureka
+            | SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader => header.to_sol_update_msg(),
+            SupportedIbcInterfaces::IbcGo08Wasm => {
+                header.to_sol_update_msg().map(wasm::wrap_client_message)
+            }
+        }
+        .map(Bytes::from)
+        .map_err(|err| {
+            fatal_error(
+                "invalid_header",
+                format!("unable to decode header sp1 proof: {}", ErrorReporter(err)),
+                None,
+            )
+        })
     }
 
     async fn encode_proof(&self, _: &Extensions, proof: Value) -> RpcResult<Bytes> {
-        serde_json::from_value::<MembershipProof>(proof)
-            .map_err(|err| {
-                ErrorObject::owned(
-                    FATAL_JSONRPC_ERROR_CODE,
-                    format!("unable to deserialize proof: {}", ErrorReporter(err)),
-                    None::<()>,
-                )
-            })
-            .map(|proof| match self.ibc_interface {
-                SupportedIbcInterfaces::SolidityIbcEureka => proof.abi_encode(),
-            })
-            .map(Bytes::from)
+        let proof = serde_json::from_value::<EncodableProof>(proof).map_err(|err| {
+            fatal_error(
+                "invalid_proof",
+                format!("unable to deserialize proof: {}", ErrorReporter(err)),
+                None,
+            )
+        })?;
+
+        match self.ibc_interface {
+            // NOTE: ibc-go passes proofs to the wasm light client as is
+            SupportedIbcInterfaces::SolidityIbcEureka
+            | SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader
+            | SupportedIbcInterfaces::IbcGo08Wasm => proof.to_sol_membership_proof(),
+        }
+        .map(Bytes::from)
+        .map_err(|err| {
+            fatal_error(
+                "invalid_proof",
+                err,
+                Some(json!({
+                    "proof_type": proof.kind(),
+                })),
+            )
+        })
     }
 }
 
 impl Module {
-    /// Decode a consensus state from bytes
+    /// How the states of a counterparty client of type `client_type` are re-encoded for the ibc
+    /// interface of this instance
     /// # Errors
-    /// Fails if the consensus state cannot be decoded
-    pub fn decode_consensus_state(&self, consensus_state: &[u8]) -> RpcResult<ConsensusState> {
-        match self.ibc_interface {
-            SupportedIbcInterfaces::SolidityIbcEureka => {
-                ConsensusState::abi_decode(consensus_state, false).map_err(|err| {
-                    ErrorObject::owned(
-                        FATAL_JSONRPC_ERROR_CODE,
-                        format!("unable to decode consensus state: {}", ErrorReporter(err)),
-                        None::<()>,
-                    )
+    /// Fails if the combination of counterparty client type and ibc interface is not supported
+    pub fn counterparty_reencoding(&self, client_type: &ClientType<'_>) -> RpcResult<Reencoding> {
+        match (&self.ibc_interface, client_type.as_str()) {
+            (
+                SupportedIbcInterfaces::SolidityIbcEureka
+                | SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader
+                | SupportedIbcInterfaces::IbcGo08Wasm,
+                ibc_eureka_types::SP1_ICS07_CLIENT_TYPE | ibc_eureka_types::MOCK_CLIENT_TYPE,
+            ) => Ok(Reencoding::PassThrough),
+            // the ethereum clients of the tracked cosmos chain are hosted by 08-wasm, which stores
+            // their states as `Any`-wrapped 08-wasm states
+            (_, ClientType::ETHEREUM_MAINNET | ClientType::ETHEREUM_MINIMAL) => {
+                let checksum = self
+                    .counterparty_wasm_checksums
+                    .get(client_type.as_str())
+                    .ok_or_else(|| {
+                        fatal_error(
+                            "missing_counterparty_wasm_checksum",
+                            format!(
+                                "the checksum of the 08-wasm code of `{client_type}` counterparty \
+                                clients is not configured"
+                            ),
+                            Some(json!({
+                                "client_type": client_type,
+                            })),
+                        )
+                    })?;
+
+                Ok(Reencoding::WrapWasm {
+                    checksum: *checksum,
                 })
             }
+            (ibc_interface, _) => Err(fatal_error(
+                "unsupported_counterparty_client_type",
+                format!(
+                    "re-encoding the states of a `{client_type}` counterparty client is not \
+                    supported for the `{}` ibc interface",
+                    String::from(ibc_interface.clone())
+                ),
+                Some(json!({
+                    "client_type": client_type,
+                })),
+            )),
         }
     }
 
+    /// Decode a consensus state from bytes
+    /// # Errors
+    /// Fails if the consensus state cannot be decoded
+    pub fn decode_consensus_state(&self, consensus_state: &[u8]) -> RpcResult<ConsensusState> {
+        let consensus_state = match self.ibc_interface {
+            SupportedIbcInterfaces::SolidityIbcEureka
+            | SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader => consensus_state.to_vec(),
+            SupportedIbcInterfaces::IbcGo08Wasm => wasm::unwrap_consensus_state(consensus_state)
+                .map_err(|err| fatal_error("invalid_consensus_state", err, None))?,
+        };
+
+        ConsensusState::abi_decode(&consensus_state, false).map_err(|err| {
+            fatal_error(
+                "invalid_consensus_state",
+                format!("unable to decode consensus state: {}", ErrorReporter(err)),
+                None,
+            )
+        })
+    }
+
     /// Decode a client state from bytes
     /// # Errors
-    /// Fails if the client state cannot be decoded
+    /// Fails if the client state cannot be decoded, or if its zk algorithm is not supported
     pub fn decode_client_state(&self, client_state: &[u8]) -> RpcResult<ClientState> {
-        match self.ibc_interface {
-            SupportedIbcInterfaces::SolidityIbcEureka => {
-                ClientState::abi_decode(client_state, false).map_err(|err| {
-                    ErrorObject::owned(
-                        FATAL_JSONRPC_ERROR_CODE,
-                        format!("unable to decode client state: {}", ErrorReporter(err)),
-                        None::<()>,
-                    )
-                })
-            }
-        }
+        let client_state = match self.ibc_interface {
+            SupportedIbcInterfaces::SolidityIbcEureka
+            | SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader => client_state.to_vec(),
+            SupportedIbcInterfaces::IbcGo08Wasm => wasm::unwrap_client_state(client_state)
+                .map_err(|err| fatal_error("invalid_client_state", err, None))?,
+        };
+
+        let cs = ClientState::abi_decode(&client_state, false).map_err(|err| {
+            fatal_error(
+                "invalid_client_state",
+                format!("unable to decode client state: {}", ErrorReporter(err)),
+                None,
+            )
+        })?;
+
+        zk_algorithm(&cs)?;
+
+        Ok(cs)
     }
 }
 
@@ -262,6 +479,8 @@ impl TryFrom<String> for SupportedIbcInterfaces {
     fn try_from(value: String) -> Result<Self, Self::Error> {
         match &*value {
             SOL_IBC_EUREKA_INTERFACE => Ok(Self::SolidityIbcEureka),
+            SOL_IBC_EUREKA_PROTOBUF_HEADER_INTERFACE => Ok(Self::SolidityIbcEurekaProtobufHeader),
+            IBC_GO_08_WASM_INTERFACE => Ok(Self::IbcGo08Wasm),
             _ => Err(format!("unsupported IBC interface: `{value}`")),
         }
     }
@@ -271,6 +490,57 @@ impl From<SupportedIbcInterfaces> for String {
     fn from(value: SupportedIbcInterfaces) -> Self {
         match value {
             SupportedIbcInterfaces::SolidityIbcEureka => SOL_IBC_EUREKA_INTERFACE.to_string(),
+            SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader => {
+                SOL_IBC_EUREKA_PROTOBUF_HEADER_INTERFACE.to_string()
+            }
+            SupportedIbcInterfaces::IbcGo08Wasm => IBC_GO_08_WASM_INTERFACE.to_string(),
         }
     }
 }
+
+impl TryFrom<u8> for SupportedZkAlgorithms {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match SupportedZkAlgorithm::try_from(value) {
+            Ok(SupportedZkAlgorithm::Groth16) => Ok(Self::Groth16),
+            Ok(SupportedZkAlgorithm::Plonk) => Ok(Self::Plonk),
+            _ => Err(format!("unsupported zk algorithm: `{value}`")),
+        }
+    }
+}
+
+impl From<SupportedZkAlgorithms> for u8 {
+    fn from(value: SupportedZkAlgorithms) -> Self {
+        match value {
+            SupportedZkAlgorithms::Groth16 => SupportedZkAlgorithm::Groth16.into(),
+            SupportedZkAlgorithms::Plonk => SupportedZkAlgorithm::Plonk.into(),
+        }
+    }
+}
+
+/// Read the zk algorithm of a client state.
+/// # Errors
+/// Fails if the zk algorithm is not supported
+pub fn zk_algorithm(client_state: &ClientState) -> RpcResult<SupportedZkAlgorithms> {
+    SupportedZkAlgorithms::try_from(client_state.zkAlgorithm).map_err(|err| {
+        fatal_error(
+            "unsupported_zk_algorithm",
+            err,
+            Some(json!({
+                "zk_algorithm": client_state.zkAlgorithm,
+            })),
+        )
+    })
+}
+
+/// Build a fatal JSON-RPC error, and count it in the metrics.
+fn fatal_error(
+    kind: &'static str,
+    message: impl Into<String>,
+    data: Option<Value>,
+) -> ErrorObjectOwned {
+    metrics().record_error(METRICS_MODULE, kind);
+
+    ErrorObjectOwned::owned(FATAL_JSONRPC_ERROR_CODE, message, data)
+}
@@ -0,0 +1,237 @@
+//! Proofs encoded by the SP1 ICS07 Light Client Module
+
+use alloy::sol_types::SolValue;
+use serde::{Deserialize, Deserializer};
+use sp1_ics07_tendermint_solidity::{
+    IICS07TendermintMsgs::ConsensusState,
+    IMembershipMsgs::{
+        KVPair, MembershipOutput, MembershipProof, MembershipProofType,
+        SP1MembershipAndUpdateClientProof, SP1MembershipProof,
+    },
+    ISP1Msgs::SP1Proof,
+    IUpdateClientAndMembershipMsgs::UcAndMembershipOutput,
+};
+
+/// The proofs accepted by `encode_proof`: an [`Sp1Ics07Proof`], or a bare ABI `MembershipProof`
+/// as accepted before proofs were tagged by `type`.
+#[derive(Debug, Clone, serde::Serialize, schemars::JsonSchema)]
+#[serde(untagged)]
+pub enum EncodableProof {
+    /// A proof tagged by `type`
+    Tagged(Sp1Ics07Proof),
+    /// A legacy `MembershipProof`, encoded as is
+    Legacy(#[schemars(with = "schema::MembershipProof")] MembershipProof),
+}
+
+impl<'de> Deserialize<'de> for EncodableProof {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+
+        // NOTE: only proofs without a `type` fall back to the legacy proof, so that the errors of
+        // malformed tagged proofs are not hidden behind a generic untagged error
+        if value.get("type").is_some() {
+            Sp1Ics07Proof::deserialize(value).map(Self::Tagged)
+        } else {
+            MembershipProof::deserialize(value).map(Self::Legacy)
+        }
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+impl EncodableProof {
+    /// Encode the proof as the ABI `MembershipProof` expected by `membership` of the SP1 ICS07
+    /// Tendermint contract.
+    /// # Errors
+    /// Fails if a tagged proof cannot be encoded, see [`Sp1Ics07Proof::to_sol_membership_proof`]
+    pub fn to_sol_membership_proof(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Tagged(proof) => proof.to_sol_membership_proof(),
+            Self::Legacy(proof) => Ok(proof.abi_encode()),
+        }
+    }
+
+    /// The name of the kind of the proof, as in its `type` tag.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Tagged(proof) => proof.kind(),
+            Self::Legacy(_) => "legacy_membership",
+        }
+    }
+}
+
+/// The proofs accepted by `encode_proof`, tagged by `type`.
+///
+/// The key/value pairs a proof covers are committed to in the public values of its SP1 proof,
+/// and are checked against the kind of the proof before encoding.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Sp1Ics07Proof {
+    /// A proof that a single key has a value, against a trusted consensus state
+    Membership {
+        /// The SP1 proof, whose public values are a `MembershipOutput` with a single non-empty
+        /// value
+        #[schemars(with = "schema::Sp1Proof")]
+        sp1_proof: SP1Proof,
+        /// The trusted consensus state the proof is verified against
+        #[schemars(with = "schema::ConsensusState")]
+        trusted_consensus_state: ConsensusState,
+    },
+    /// A proof that a single key has no value, against a trusted consensus state
+    NonMembership {
+        /// The SP1 proof, whose public values are a `MembershipOutput` with a single empty
+        /// value
+        #[schemars(with = "schema::Sp1Proof")]
+        sp1_proof: SP1Proof,
+        /// The trusted consensus state the proof is verified against
+        #[schemars(with = "schema::ConsensusState")]
+        trusted_consensus_state: ConsensusState,
+    },
+    /// A single proof of the (non-)membership of many keys, against a trusted consensus state
+    BatchedMembership {
+        /// The SP1 proof, whose public values are a `MembershipOutput` with at least one
+        /// key/value pair. Empty values prove non-membership.
+        #[schemars(with = "schema::Sp1Proof")]
+        sp1_proof: SP1Proof,
+        /// The trusted consensus state the proof is verified against
+        #[schemars(with = "schema::ConsensusState")]
+        trusted_consensus_state: ConsensusState,
+    },
+    /// A single proof of a client update and of the (non-)membership of many keys at the
+    /// updated height
+    UpdateClientAndMembership {
+        /// The SP1 proof, whose public values are an `UcAndMembershipOutput` with at least one
+        /// key/value pair. Empty values prove non-membership.
+        #[schemars(with = "schema::Sp1Proof")]
+        sp1_proof: SP1Proof,
+    },
+}
+
+impl Sp1Ics07Proof {
+    /// Encode the proof as the ABI `MembershipProof` expected by `membership` of the SP1 ICS07
+    /// Tendermint contract.
+    /// # Errors
+    /// Fails if the public values of the SP1 proof cannot be decoded, or if the key/value pairs
+    /// they commit to do not match the kind of the proof
+    pub fn to_sol_membership_proof(&self) -> Result<Vec<u8>, String> {
+        let (proof_type, proof) = match self {
+            Self::Membership {
+                sp1_proof,
+                trusted_consensus_state,
+            }
+            | Self::NonMembership {
+                sp1_proof,
+                trusted_consensus_state,
+            }
+            | Self::BatchedMembership {
+                sp1_proof,
+                trusted_consensus_state,
+            } => {
+                let output = MembershipOutput::abi_decode(&sp1_proof.publicValues, true)
+                    .map_err(|err| format!("unable to decode membership output: {err}"))?;
+                self.check_kv_pairs(&output.kvPairs)?;
+
+                (
+                    MembershipProofType::SP1MembershipProof,
+                    SP1MembershipProof {
+                        sp1Proof: sp1_proof.clone(),
+                        trustedConsensusState: trusted_consensus_state.clone(),
+                    }
+                    .abi_encode(),
+                )
+            }
+            Self::UpdateClientAndMembership { sp1_proof } => {
+                let output = UcAndMembershipOutput::abi_decode(&sp1_proof.publicValues, true)
+                    .map_err(|err| {
+                        format!("unable to decode update client and membership output: {err}")
+                    })?;
+                self.check_kv_pairs(&output.kvPairs)?;
+
+                (
+                    MembershipProofType::SP1MembershipAndUpdateClientProof,
+                    SP1MembershipAndUpdateClientProof {
+                        sp1Proof: sp1_proof.clone(),
+                    }
+                    .abi_encode(),
+                )
+            }
+        };
+
+        Ok(MembershipProof {
+            proofType: proof_type.into(),
+            proof: proof.into(),
+        }
+        .abi_encode())
+    }
+
+    /// The name of the kind of the proof, as in its `type` tag.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Membership { .. } => "membership",
+            Self::NonMembership { .. } => "non_membership",
+            Self::BatchedMembership { .. } => "batched_membership",
+            Self::UpdateClientAndMembership { .. } => "update_client_and_membership",
+        }
+    }
+
+    fn check_kv_pairs(&self, kv_pairs: &[KVPair]) -> Result<(), String> {
+        let valid = match (self, kv_pairs) {
+            (Self::Membership { .. }, [kv_pair]) => !kv_pair.value.is_empty(),
+            (Self::NonMembership { .. }, [kv_pair]) => kv_pair.value.is_empty(),
+            (Self::BatchedMembership { .. } | Self::UpdateClientAndMembership { .. }, _) => {
+                !kv_pairs.is_empty()
+            }
+            _ => false,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(format!(
+                "a `{}` proof cannot prove {} key/value pair(s) with {} empty value(s)",
+                self.kind(),
+                kv_pairs.len(),
+                kv_pairs.iter().filter(|kv| kv.value.is_empty()).count(),
+            ))
+        }
+    }
+}
+
+/// JSON schemas of the ABI types in proofs, as serialized by their serde derives
+#[allow(non_snake_case, dead_code)]
+mod schema {
+    use schemars::JsonSchema;
+
+    /// An SP1 proof
+    #[derive(JsonSchema)]
+    #[schemars(rename = "SP1Proof")]
+    pub struct Sp1Proof {
+        /// The verification key of the SP1 program, as 0x-prefixed hex
+        vKey: String,
+        /// The public values committed to by the program, as 0x-prefixed hex
+        publicValues: String,
+        /// The proof, as 0x-prefixed hex
+        proof: String,
+    }
+
+    /// A consensus state of the SP1 ICS07 Tendermint contract
+    #[derive(JsonSchema)]
+    pub struct ConsensusState {
+        /// The timestamp of the consensus state, in nanoseconds
+        timestamp: u64,
+        /// The app hash, as 0x-prefixed hex
+        root: String,
+        /// The hash of the next validator set, as 0x-prefixed hex
+        nextValidatorsHash: String,
+    }
+
+    /// An ABI `MembershipProof`
+    #[derive(JsonSchema)]
+    pub struct MembershipProof {
+        /// The `MembershipProofType` of the proof
+        proofType: u8,
+        /// The ABI encoded proof of that type, as 0x-prefixed hex
+        proof: String,
+    }
+}
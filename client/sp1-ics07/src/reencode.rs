@@ -0,0 +1,16 @@
+//! Re-encoding of counterparty client and consensus states
+
+use alloy::primitives::B256;
+
+/// How the states of a counterparty client are re-encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reencoding {
+    /// The states are already encoded as expected, and are returned as is
+    PassThrough,
+    /// The states are those of a wasm light client, and are wrapped into the `Any`-wrapped
+    /// 08-wasm states it is stored as
+    WrapWasm {
+        /// The checksum of the stored wasm light client code
+        checksum: B256,
+    },
+}
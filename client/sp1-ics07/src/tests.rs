@@ -0,0 +1,373 @@
+//! Tests of the encodings of the SP1 ICS07 Light Client Module
+
+use std::collections::BTreeMap;
+
+use alloy::{primitives::B256, sol_types::SolValue};
+use ibc_eureka_types::{
+    IBC_GO_08_WASM_INTERFACE, SOL_IBC_EUREKA_INTERFACE, SOL_IBC_EUREKA_PROTOBUF_HEADER_INTERFACE,
+    SP1_ICS07_CLIENT_TYPE,
+};
+use ibc_proto::{
+    google::protobuf::Any,
+    ibc::{core::client::v1::Height, lightclients::wasm::v1::ClientState as WasmClientState},
+};
+use jsonrpsee::Extensions;
+use prost::Message;
+use serde_json::{json, Value};
+use sp1_ics07_tendermint_solidity::{
+    IICS02ClientMsgs::Height as SolHeight,
+    IICS07TendermintMsgs::{ClientState, ConsensusState, TrustThreshold},
+    IMembershipMsgs::MembershipProof,
+    ISP1Msgs::SP1Proof,
+    IUpdateClientMsgs::MsgUpdateClient,
+};
+use unionlabs::bytes::Bytes;
+use voyager_message::{core::ClientType, module::ClientModuleServer};
+
+use crate::{
+    header::Sp1Header,
+    proof::{EncodableProof, Sp1Ics07Proof},
+    wasm, zk_algorithm, Module, SupportedIbcInterfaces, SupportedZkAlgorithms,
+};
+
+/// The checksum of the 08-wasm code of the ethereum counterparty clients
+const ETHEREUM_WASM_CHECKSUM: B256 = B256::repeat_byte(8);
+
+fn sp1_proof() -> SP1Proof {
+    SP1Proof {
+        vKey: B256::repeat_byte(1),
+        publicValues: vec![2; 64].into(),
+        proof: vec![3; 128].into(),
+    }
+}
+
+fn module(ibc_interface: SupportedIbcInterfaces) -> Module {
+    Module {
+        ibc_interface,
+        counterparty_wasm_checksums: BTreeMap::from([(
+            ClientType::ETHEREUM_MAINNET.to_string(),
+            ETHEREUM_WASM_CHECKSUM,
+        )]),
+    }
+}
+
+fn client_state() -> ClientState {
+    ClientState {
+        chainId: "cosmoshub-4".to_string(),
+        trustLevel: TrustThreshold {
+            numerator: 1,
+            denominator: 3,
+        },
+        latestHeight: SolHeight {
+            revisionNumber: 4,
+            revisionHeight: 1_000,
+        },
+        trustingPeriod: 1_209_600,
+        unbondingPeriod: 1_814_400,
+        isFrozen: false,
+        zkAlgorithm: 1,
+    }
+}
+
+fn consensus_state() -> ConsensusState {
+    ConsensusState {
+        timestamp: 1,
+        root: B256::repeat_byte(4),
+        nextValidatorsHash: B256::repeat_byte(5),
+    }
+}
+
+fn tagged_proofs() -> Vec<(&'static str, Value)> {
+    let membership = |kind| {
+        json!({
+            "type": kind,
+            "sp1_proof": sp1_proof(),
+            "trusted_consensus_state": consensus_state(),
+        })
+    };
+
+    vec![
+        ("membership", membership("membership")),
+        ("non_membership", membership("non_membership")),
+        ("batched_membership", membership("batched_membership")),
+        (
+            "update_client_and_membership",
+            json!({
+                "type": "update_client_and_membership",
+                "sp1_proof": sp1_proof(),
+            }),
+        ),
+    ]
+}
+
+#[test]
+fn ibc_interfaces_round_trip_through_their_names() {
+    for name in [
+        SOL_IBC_EUREKA_INTERFACE,
+        SOL_IBC_EUREKA_PROTOBUF_HEADER_INTERFACE,
+        IBC_GO_08_WASM_INTERFACE,
+    ] {
+        let ibc_interface = SupportedIbcInterfaces::try_from(name.to_string()).unwrap();
+
+        assert_eq!(String::from(ibc_interface), name);
+    }
+
+    assert!(SupportedIbcInterfaces::try_from("ibc-go-v7/07-tendermint".to_string()).is_err());
+}
+
+#[test]
+fn sp1_header_is_read_from_the_prove_response() {
+    let sp1_proof = sp1_proof().abi_encode();
+
+    // the other fields of the `ProveResponse` of the client update plugin are ignored
+    let header = serde_json::from_value::<Sp1Header>(json!({
+        "trusted_consensus_state": { "timestamp": 1 },
+        "proposed_header": { "signed_header": {} },
+        "sp1_proof": sp1_proof,
+    }))
+    .unwrap();
+
+    assert_eq!(header, Sp1Header { sp1_proof });
+}
+
+#[test]
+fn sp1_header_is_encoded_as_a_sol_update_msg() {
+    let header = Sp1Header {
+        sp1_proof: sp1_proof().abi_encode(),
+    };
+
+    let msg = MsgUpdateClient::abi_decode(&header.to_sol_update_msg().unwrap(), true).unwrap();
+
+    assert_eq!(msg.sp1Proof.vKey, sp1_proof().vKey);
+    assert_eq!(msg.sp1Proof.publicValues, sp1_proof().publicValues);
+    assert_eq!(msg.sp1Proof.proof, sp1_proof().proof);
+}
+
+#[test]
+fn sp1_header_requires_an_abi_encoded_proof() {
+    let header = Sp1Header {
+        sp1_proof: vec![1, 2, 3],
+    };
+
+    assert!(header.to_sol_update_msg().is_err());
+}
+
+#[test]
+fn tagged_proofs_round_trip_through_json() {
+    for (kind, json) in tagged_proofs() {
+        let proof = serde_json::from_value::<EncodableProof>(json.clone()).unwrap();
+
+        assert!(matches!(proof, EncodableProof::Tagged(_)));
+        assert_eq!(proof.kind(), kind);
+        assert_eq!(serde_json::to_value(&proof).unwrap(), json);
+    }
+}
+
+#[test]
+fn legacy_proofs_round_trip_as_is() {
+    let legacy = MembershipProof {
+        proofType: 1,
+        proof: vec![1, 2, 3].into(),
+    };
+    let json = serde_json::to_value(&legacy).unwrap();
+
+    let proof = serde_json::from_value::<EncodableProof>(json.clone()).unwrap();
+
+    assert!(matches!(proof, EncodableProof::Legacy(_)));
+    assert_eq!(proof.kind(), "legacy_membership");
+    assert_eq!(serde_json::to_value(&proof).unwrap(), json);
+
+    let encoded = proof.to_sol_membership_proof().unwrap();
+    assert_eq!(encoded, legacy.abi_encode());
+
+    let decoded = MembershipProof::abi_decode(&encoded, true).unwrap();
+    assert_eq!(decoded.proofType, legacy.proofType);
+    assert_eq!(decoded.proof, legacy.proof);
+}
+
+#[test]
+fn malformed_tagged_proofs_do_not_fall_back_to_legacy_proofs() {
+    let (_, mut json) = tagged_proofs().remove(0);
+    json["proofType"] = json!(1);
+
+    let err = serde_json::from_value::<EncodableProof>(json)
+        .unwrap_err()
+        .to_string();
+
+    assert!(err.contains("unknown field `proofType`"), "{err}");
+
+    let err = serde_json::from_value::<EncodableProof>(json!({ "type": "mystery" }))
+        .unwrap_err()
+        .to_string();
+
+    assert!(err.contains("unknown variant `mystery`"), "{err}");
+}
+
+#[test]
+fn tagged_proofs_require_decodable_public_values() {
+    for (kind, json) in tagged_proofs() {
+        let proof = serde_json::from_value::<Sp1Ics07Proof>(json).unwrap();
+
+        let err = proof.to_sol_membership_proof().unwrap_err();
+
+        assert!(err.starts_with("unable to decode"), "{kind}: {err}");
+    }
+}
+
+#[test]
+fn proof_schema_covers_every_kind_of_proof() {
+    let schema = serde_json::to_string(&schemars::schema_for!(EncodableProof)).unwrap();
+
+    for (kind, _) in tagged_proofs() {
+        assert!(schema.contains(&format!("\"{kind}\"")), "{kind}");
+    }
+    for field in ["vKey", "nextValidatorsHash", "proofType"] {
+        assert!(schema.contains(&format!("\"{field}\"")), "{field}");
+    }
+}
+
+#[test]
+fn wasm_states_round_trip_through_their_any_wrapping() {
+    let data = vec![1, 2, 3];
+    let client_state = wasm::wrap_client_state(
+        data.clone(),
+        vec![4; 32],
+        Some(Height {
+            revision_number: 0,
+            revision_height: 5,
+        }),
+    );
+    let consensus_state = wasm::wrap_consensus_state(data.clone());
+
+    assert_eq!(wasm::unwrap_client_state(&client_state).unwrap(), data);
+    assert_eq!(
+        wasm::unwrap_consensus_state(&consensus_state).unwrap(),
+        data
+    );
+
+    // the type url of the wrapped state is checked
+    assert!(wasm::unwrap_client_state(&consensus_state).is_err());
+    assert!(wasm::unwrap_consensus_state(&client_state).is_err());
+    assert!(wasm::unwrap_client_state(&wasm::wrap_client_message(data)).is_err());
+}
+
+#[tokio::test]
+async fn decoded_client_states_round_trip_through_encoding() {
+    let module = module(SupportedIbcInterfaces::SolidityIbcEureka);
+    let client_state = client_state().abi_encode();
+
+    let decoded = ClientModuleServer::decode_client_state(
+        &module,
+        &Extensions::new(),
+        Bytes::from(client_state.clone()),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(decoded, serde_json::to_value(client_state()).unwrap());
+
+    let encoded =
+        ClientModuleServer::encode_client_state(&module, &Extensions::new(), decoded, Value::Null)
+            .await
+            .unwrap();
+
+    assert_eq!(encoded.into_vec(), client_state);
+}
+
+#[test]
+fn zk_algorithm_is_read_from_the_client_state() {
+    assert_eq!(
+        zk_algorithm(&client_state()).unwrap(),
+        SupportedZkAlgorithms::Plonk
+    );
+    assert_eq!(
+        zk_algorithm(&ClientState {
+            zkAlgorithm: 0,
+            ..client_state()
+        })
+        .unwrap(),
+        SupportedZkAlgorithms::Groth16
+    );
+    assert!(zk_algorithm(&ClientState {
+        zkAlgorithm: 7,
+        ..client_state()
+    })
+    .is_err());
+}
+
+#[tokio::test]
+async fn ethereum_counterparty_states_are_wrapped_for_08_wasm() {
+    let ethereum = ClientType::new(ClientType::ETHEREUM_MAINNET);
+    let (client_state, consensus_state) = (vec![1, 2, 3], vec![4, 5, 6]);
+
+    for ibc_interface in [
+        SupportedIbcInterfaces::SolidityIbcEureka,
+        SupportedIbcInterfaces::SolidityIbcEurekaProtobufHeader,
+        SupportedIbcInterfaces::IbcGo08Wasm,
+    ] {
+        let module = module(ibc_interface);
+
+        let wrapped_client_state = ClientModuleServer::reencode_counterparty_client_state(
+            &module,
+            &Extensions::new(),
+            Bytes::from(client_state.clone()),
+            ethereum.clone(),
+        )
+        .await
+        .unwrap()
+        .into_vec();
+        let wrapped_consensus_state = ClientModuleServer::reencode_counterparty_consensus_state(
+            &module,
+            &Extensions::new(),
+            Bytes::from(consensus_state.clone()),
+            ethereum.clone(),
+        )
+        .await
+        .unwrap()
+        .into_vec();
+
+        assert_eq!(
+            wasm::unwrap_client_state(&wrapped_client_state).unwrap(),
+            client_state
+        );
+        assert_eq!(
+            wasm::unwrap_consensus_state(&wrapped_consensus_state).unwrap(),
+            consensus_state
+        );
+
+        let any = Any::decode(&*wrapped_client_state).unwrap();
+        let wasm_client_state = WasmClientState::decode(&*any.value).unwrap();
+        assert_eq!(wasm_client_state.checksum, ETHEREUM_WASM_CHECKSUM.to_vec());
+        assert_eq!(wasm_client_state.latest_height, None);
+    }
+}
+
+#[tokio::test]
+async fn sp1_counterparty_states_are_passed_through() {
+    let client_state = vec![1, 2, 3];
+
+    let reencoded = ClientModuleServer::reencode_counterparty_client_state(
+        &module(SupportedIbcInterfaces::IbcGo08Wasm),
+        &Extensions::new(),
+        Bytes::from(client_state.clone()),
+        ClientType::new(SP1_ICS07_CLIENT_TYPE),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(reencoded.into_vec(), client_state);
+}
+
+#[test]
+fn unsupported_counterparty_reencodings_are_rejected() {
+    let module = module(SupportedIbcInterfaces::SolidityIbcEureka);
+
+    assert!(module
+        .counterparty_reencoding(&ClientType::new("07-tendermint"))
+        .is_err());
+
+    // the checksum of the ethereum minimal clients is not configured
+    assert!(module
+        .counterparty_reencoding(&ClientType::new(ClientType::ETHEREUM_MINIMAL))
+        .is_err());
+}
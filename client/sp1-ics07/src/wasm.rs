@@ -0,0 +1,95 @@
+//! `Any`-wrapped 08-wasm states and client messages, as stored and expected by ibc-go
+
+use ibc_proto::{
+    google::protobuf::Any,
+    ibc::{
+        core::client::v1::Height,
+        lightclients::wasm::v1::{ClientMessage, ClientState, ConsensusState},
+    },
+};
+use prost::Message;
+
+/// The type URL of an 08-wasm client state
+pub const WASM_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ClientState";
+
+/// The type URL of an 08-wasm consensus state
+pub const WASM_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ConsensusState";
+
+/// The type URL of an 08-wasm client message
+pub const WASM_CLIENT_MESSAGE_TYPE_URL: &str = "/ibc.lightclients.wasm.v1.ClientMessage";
+
+/// Wrap the client state of a wasm light client into an `Any`-wrapped 08-wasm client state.
+/// The latest height is only stored by the 08-wasm of ibc-go v8, later versions read it from the
+/// wasm light client.
+#[must_use]
+pub fn wrap_client_state(
+    data: Vec<u8>,
+    checksum: Vec<u8>,
+    latest_height: Option<Height>,
+) -> Vec<u8> {
+    wrap_any(
+        WASM_CLIENT_STATE_TYPE_URL,
+        &ClientState {
+            data,
+            checksum,
+            latest_height,
+        },
+    )
+}
+
+/// Wrap the consensus state of a wasm light client into an `Any`-wrapped 08-wasm consensus
+/// state.
+#[must_use]
+pub fn wrap_consensus_state(data: Vec<u8>) -> Vec<u8> {
+    wrap_any(WASM_CONSENSUS_STATE_TYPE_URL, &ConsensusState { data })
+}
+
+/// Wrap a client message of a wasm light client into an `Any`-wrapped 08-wasm client message.
+#[must_use]
+pub fn wrap_client_message(data: Vec<u8>) -> Vec<u8> {
+    wrap_any(WASM_CLIENT_MESSAGE_TYPE_URL, &ClientMessage { data })
+}
+
+/// Unwrap an `Any`-wrapped 08-wasm client state to the client state of the wasm light client.
+/// # Errors
+/// Fails if the bytes are not an `Any`-wrapped 08-wasm client state
+pub fn unwrap_client_state(client_state: &[u8]) -> Result<Vec<u8>, String> {
+    let value = unwrap_any(client_state, WASM_CLIENT_STATE_TYPE_URL)?;
+
+    ClientState::decode(&*value)
+        .map(|client_state| client_state.data)
+        .map_err(|err| format!("unable to decode 08-wasm client state: {err}"))
+}
+
+/// Unwrap an `Any`-wrapped 08-wasm consensus state to the consensus state of the wasm light
+/// client.
+/// # Errors
+/// Fails if the bytes are not an `Any`-wrapped 08-wasm consensus state
+pub fn unwrap_consensus_state(consensus_state: &[u8]) -> Result<Vec<u8>, String> {
+    let value = unwrap_any(consensus_state, WASM_CONSENSUS_STATE_TYPE_URL)?;
+
+    ConsensusState::decode(&*value)
+        .map(|consensus_state| consensus_state.data)
+        .map_err(|err| format!("unable to decode 08-wasm consensus state: {err}"))
+}
+
+fn wrap_any(type_url: &str, message: &impl Message) -> Vec<u8> {
+    Any {
+        type_url: type_url.to_owned(),
+        value: message.encode_to_vec(),
+    }
+    .encode_to_vec()
+}
+
+fn unwrap_any(bytes: &[u8], expected_type_url: &str) -> Result<Vec<u8>, String> {
+    let any = Any::decode(bytes).map_err(|err| format!("unable to decode `Any`: {err}"))?;
+
+    if any.type_url != expected_type_url {
+        return Err(format!(
+            "unexpected type url: expected `{expected_type_url}`, but found `{}`",
+            any.type_url
+        ));
+    }
+
+    Ok(any.value)
+}
@@ -0,0 +1,371 @@
+//! Failover and quorum client for beacon API endpoints
+
+use std::{future::Future, sync::Arc, time::Instant};
+
+use beacon_api::client::BeaconApiClient;
+use futures::future::join_all;
+use ibc_eureka_telemetry::metrics::metrics;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::{
+    config::FailoverConfig,
+    health::{attempt_order, EndpointHealth},
+    retry::{retry, RateLimitConfig, RetryConfig, TokenBucket},
+};
+
+/// The name of the beacon API in the health report
+pub const BEACON_API_DEPENDENCY: &str = "beacon_api";
+
+/// An error returned by the [`FailoverBeaconClient`]
+#[derive(Debug, thiserror::Error)]
+pub enum BeaconRequestError {
+    /// The request failed on all endpoints. This is the error of the last endpoint tried.
+    #[error("beacon api request failed")]
+    Request(#[source] beacon_api::errors::Error),
+    /// Not enough endpoints returned matching responses.
+    #[error("no quorum of {threshold} matching responses from the beacon api endpoints")]
+    NoQuorum {
+        /// The number of matching responses required
+        threshold: usize,
+    },
+}
+
+impl BeaconRequestError {
+    /// Whether the request may succeed if it is retried. Errors returned by healthy endpoints,
+    /// such as a block that is not found, are not retried.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Request(err) => is_endpoint_failure(err),
+            // the endpoints may agree once they have caught up with each other
+            Self::NoQuorum { .. } => true,
+        }
+    }
+}
+
+/// A beacon API client that sends each request to the first healthy endpoint, failing over to
+/// the next one on errors, and that can require a quorum of matching responses for critical
+/// reads
+#[derive(Debug, Clone)]
+pub struct FailoverBeaconClient {
+    clients: Arc<[Endpoint]>,
+    failover: FailoverConfig,
+    quorum_threshold: Option<usize>,
+    retry: RetryConfig,
+}
+
+#[derive(Debug, Clone)]
+struct Endpoint {
+    client: BeaconApiClient,
+    health: EndpointHealth,
+    rate_limiter: Option<TokenBucket>,
+}
+
+impl Endpoint {
+    async fn call<T, F, Fut>(&self, request: &F) -> Result<T, beacon_api::errors::Error>
+    where
+        F: Fn(BeaconApiClient) -> Fut,
+        Fut: Future<Output = Result<T, beacon_api::errors::Error>>,
+    {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+
+        let start = Instant::now();
+        let response = request(self.client.clone()).await;
+        metrics().record_rpc_call("beacon", "beacon_api", response.is_ok(), start.elapsed());
+
+        response
+    }
+}
+
+impl FailoverBeaconClient {
+    /// Create a failover client from clients connected to each endpoint, in order of preference.
+    /// # Errors
+    /// Returns an error if there are no clients or if the quorum cannot be reached with the
+    /// number of clients.
+    pub fn new(
+        clients: Vec<(String, BeaconApiClient)>,
+        failover: FailoverConfig,
+        quorum_threshold: Option<usize>,
+    ) -> Result<Self, String> {
+        if clients.is_empty() {
+            return Err("at least one beacon api endpoint is required".to_string());
+        }
+
+        if let Some(threshold) = quorum_threshold {
+            if threshold == 0 || threshold > clients.len() {
+                return Err(format!(
+                    "quorum threshold must be between 1 and the number of beacon api endpoints \
+                    ({}), but is {threshold}",
+                    clients.len()
+                ));
+            }
+        }
+
+        Ok(Self {
+            clients: clients
+                .into_iter()
+                .map(|(url, client)| Endpoint {
+                    client,
+                    health: EndpointHealth::new(url),
+                    rate_limiter: None,
+                })
+                .collect(),
+            failover,
+            quorum_threshold,
+            retry: RetryConfig::default(),
+        })
+    }
+
+    /// Retry failed requests according to `retry`.
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Limit the request rate of each endpoint, if `rate_limit` is set.
+    #[must_use]
+    pub fn with_rate_limit(mut self, rate_limit: Option<&RateLimitConfig>) -> Self {
+        self.clients = self
+            .clients
+            .iter()
+            .map(|endpoint| Endpoint {
+                rate_limiter: rate_limit.map(TokenBucket::new),
+                ..endpoint.clone()
+            })
+            .collect();
+        self
+    }
+
+    /// The health of each endpoint, in order of preference.
+    pub fn health(&self) -> impl Iterator<Item = &EndpointHealth> {
+        self.clients.iter().map(|endpoint| &endpoint.health)
+    }
+
+    /// Run `request` against the first healthy endpoint, failing over to the next ones on
+    /// errors, and retrying with backoff if all endpoints fail.
+    /// # Errors
+    /// Returns the error of the last endpoint if the request fails on all endpoints.
+    pub async fn request<T, F, Fut>(&self, request: F) -> Result<T, BeaconRequestError>
+    where
+        F: Fn(BeaconApiClient) -> Fut,
+        Fut: Future<Output = Result<T, beacon_api::errors::Error>>,
+    {
+        let response = retry(&self.retry, BeaconRequestError::is_retryable, || {
+            self.request_once(&request)
+        })
+        .await;
+        ibc_eureka_telemetry::health::health().record(BEACON_API_DEPENDENCY, &response);
+
+        response
+    }
+
+    async fn request_once<T, F, Fut>(&self, request: &F) -> Result<T, BeaconRequestError>
+    where
+        F: Fn(BeaconApiClient) -> Fut,
+        Fut: Future<Output = Result<T, beacon_api::errors::Error>>,
+    {
+        let mut last_err = None;
+
+        for index in attempt_order(self.health()) {
+            let endpoint = &self.clients[index];
+            let health = &endpoint.health;
+
+            match endpoint.call(request).await {
+                Ok(response) => {
+                    health.record_success();
+                    return Ok(response);
+                }
+                Err(err) => {
+                    warn!(endpoint = health.url(), ?err, "beacon api request failed");
+                    if is_endpoint_failure(&err) {
+                        health.record_failure(&self.failover);
+                    }
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        Err(BeaconRequestError::Request(
+            last_err.expect("there is at least one endpoint"),
+        ))
+    }
+
+    /// Run `request` against all endpoints and require a quorum of matching responses, if a
+    /// quorum is configured. Otherwise, this is the same as [`Self::request`].
+    /// # Errors
+    /// Returns an error if no quorum of matching responses is reached, or the error of the last
+    /// endpoint if all endpoints reject the request.
+    pub async fn request_quorum<T, F, Fut>(&self, request: F) -> Result<T, BeaconRequestError>
+    where
+        T: Serialize,
+        F: Fn(BeaconApiClient) -> Fut,
+        Fut: Future<Output = Result<T, beacon_api::errors::Error>>,
+    {
+        let Some(threshold) = self.quorum_threshold else {
+            return self.request(request).await;
+        };
+
+        let response = retry(&self.retry, BeaconRequestError::is_retryable, || {
+            self.request_quorum_once(&request, threshold)
+        })
+        .await;
+        ibc_eureka_telemetry::health::health().record(BEACON_API_DEPENDENCY, &response);
+
+        response
+    }
+
+    async fn request_quorum_once<T, F, Fut>(
+        &self,
+        request: &F,
+        threshold: usize,
+    ) -> Result<T, BeaconRequestError>
+    where
+        T: Serialize,
+        F: Fn(BeaconApiClient) -> Fut,
+        Fut: Future<Output = Result<T, beacon_api::errors::Error>>,
+    {
+        let responses = join_all(
+            self.clients
+                .iter()
+                .map(|endpoint| async move { (&endpoint.health, endpoint.call(request).await) }),
+        )
+        .await;
+
+        let mut groups: Vec<(String, T, usize)> = vec![];
+        // the error of the last endpoint, if no endpoint succeeded and none of them failed
+        // because of the endpoint itself
+        let mut request_err = None;
+        let mut endpoint_failed = false;
+        for (health, response) in responses {
+            match response {
+                Ok(response) => {
+                    health.record_success();
+
+                    // responses that cannot be serialized never match any other response
+                    let Ok(key) = serde_json::to_string(&response) else {
+                        continue;
+                    };
+                    match groups
+                        .iter_mut()
+                        .find(|(group_key, _, _)| *group_key == key)
+                    {
+                        Some((_, _, count)) => *count += 1,
+                        None => groups.push((key, response, 1)),
+                    }
+                }
+                Err(err) => {
+                    warn!(endpoint = health.url(), ?err, "beacon api request failed");
+                    if is_endpoint_failure(&err) {
+                        health.record_failure(&self.failover);
+                        endpoint_failed = true;
+                    } else {
+                        request_err = Some(err);
+                    }
+                }
+            }
+        }
+
+        match request_err {
+            Some(err) if groups.is_empty() && !endpoint_failed => {
+                Err(BeaconRequestError::Request(err))
+            }
+            _ => groups
+                .into_iter()
+                .find(|(_, _, count)| *count >= threshold)
+                .map(|(_, response, _)| response)
+                .ok_or(BeaconRequestError::NoQuorum { threshold }),
+        }
+    }
+}
+
+/// Whether `err` is caused by the endpoint rather than by the request, i.e. it is a transport
+/// error, a server error or a rate limit. Other errors, such as a block that is not found, are
+/// returned by healthy endpoints as well.
+fn is_endpoint_failure(err: &beacon_api::errors::Error) -> bool {
+    let is_failure_status = |status: u16| status == 429 || status >= 500;
+
+    match err {
+        beacon_api::errors::Error::Http(err) => match err.status() {
+            Some(status) => is_failure_status(status.as_u16()),
+            None => true,
+        },
+        beacon_api::errors::Error::Internal(_) => true,
+        beacon_api::errors::Error::Other(status, _) => is_failure_status(status.as_u16()),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use beacon_api::client::BlockId;
+    use ibc_eureka_test_utils::stand_in::StandIn;
+    use serde_json::json;
+
+    use super::*;
+
+    async fn client(stand_in: &StandIn, quorum_threshold: Option<usize>) -> FailoverBeaconClient {
+        let url = stand_in.url();
+
+        FailoverBeaconClient::new(
+            vec![(url.clone(), BeaconApiClient::new(url).await.unwrap())],
+            FailoverConfig::default(),
+            quorum_threshold,
+        )
+        .unwrap()
+        .with_retry(RetryConfig {
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+        })
+    }
+
+    fn error(status: u16, message: &str) -> (u16, serde_json::Value) {
+        (
+            status,
+            json!({ "code": status, "message": message, "stacktraces": [] }),
+        )
+    }
+
+    #[tokio::test]
+    async fn not_found_errors_are_not_retried() {
+        let stand_in = StandIn::serve(|_| error(404, "NOT_FOUND: beacon block")).await;
+
+        for quorum_threshold in [None, Some(1)] {
+            let client = client(&stand_in, quorum_threshold).await;
+            let requests = stand_in.requests().len();
+
+            let err = client
+                .request_quorum(|client| async move { client.block(BlockId::Slot(1)).await })
+                .await
+                .unwrap_err();
+
+            assert!(!err.is_retryable());
+            assert_eq!(stand_in.requests().len(), requests + 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_and_rate_limits_are_retried() {
+        for status in [429, 500, 503] {
+            let stand_in = StandIn::serve(move |_| error(status, "unavailable")).await;
+            let client = client(&stand_in, None).await;
+
+            let err = client
+                .request(|client| async move { client.block(BlockId::Slot(1)).await })
+                .await
+                .unwrap_err();
+
+            assert!(err.is_retryable(), "{status} should be retryable");
+            assert_eq!(stand_in.requests().len(), 3, "{status} should be retried");
+        }
+    }
+
+    #[test]
+    fn missing_quorums_are_retryable() {
+        assert!(BeaconRequestError::NoQuorum { threshold: 2 }.is_retryable());
+    }
+}
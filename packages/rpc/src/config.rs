@@ -0,0 +1,78 @@
+//! Configuration of RPC endpoints
+
+use serde::{Deserialize, Serialize};
+
+/// One or more RPC endpoints, in order of preference
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Endpoints {
+    /// A single endpoint
+    Single(String),
+    /// Multiple endpoints, the first one is preferred
+    Multiple(Vec<String>),
+}
+
+impl Endpoints {
+    /// The endpoint URLs, in order of preference.
+    #[must_use]
+    pub fn urls(&self) -> &[String] {
+        match self {
+            Self::Single(url) => std::slice::from_ref(url),
+            Self::Multiple(urls) => urls,
+        }
+    }
+}
+
+/// When an endpoint is considered unhealthy and skipped by the failover
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct FailoverConfig {
+    /// The number of consecutive failed requests after which an endpoint is marked unhealthy.
+    pub max_consecutive_failures: u32,
+    /// How long an unhealthy endpoint is skipped for, in seconds. Unhealthy endpoints are still
+    /// tried as a last resort if all healthy endpoints fail.
+    pub cooldown_secs: u64,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            max_consecutive_failures: 3,
+            cooldown_secs: 30,
+        }
+    }
+}
+
+/// Reads that require multiple endpoints to return the same response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QuorumConfig {
+    /// The number of matching responses required.
+    pub threshold: usize,
+    /// The execution RPC methods that require a quorum. Only add methods whose requests pin a
+    /// block number, as responses for tags such as `latest` legitimately differ between
+    /// endpoints.
+    #[serde(default = "default_quorum_methods")]
+    pub methods: Vec<String>,
+}
+
+fn default_quorum_methods() -> Vec<String> {
+    vec!["eth_getProof".to_string()]
+}
+
+impl QuorumConfig {
+    /// Check that the quorum can be reached with `endpoints` endpoints.
+    /// # Errors
+    /// Returns an error if the threshold is zero or larger than the number of endpoints.
+    pub fn validate(&self, endpoints: usize) -> Result<(), String> {
+        if self.threshold == 0 || self.threshold > endpoints {
+            return Err(format!(
+                "quorum threshold must be between 1 and the number of endpoints ({endpoints}), \
+                but is {}",
+                self.threshold
+            ));
+        }
+
+        Ok(())
+    }
+}
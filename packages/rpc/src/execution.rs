@@ -0,0 +1,453 @@
+//! Failover and quorum transport for execution RPC endpoints
+
+use std::{
+    str::FromStr,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Instant,
+};
+
+use alloy::{
+    rpc::{
+        client::{BuiltInConnectionString, RpcClient},
+        json_rpc::{RequestPacket, Response, ResponsePacket, ResponsePayload},
+    },
+    transports::{BoxTransport, RpcError, TransportError, TransportErrorKind, TransportFut},
+};
+use futures::future::join_all;
+use ibc_eureka_telemetry::metrics::metrics;
+use serde_json::Value;
+use tower::Service;
+use tracing::warn;
+
+use crate::{
+    config::{Endpoints, FailoverConfig, QuorumConfig},
+    health::{attempt_order, EndpointHealth},
+    retry::{retry, RateLimitConfig, RetryConfig, TokenBucket},
+};
+
+/// The name of the execution RPC in the health report
+pub const EXECUTION_RPC_DEPENDENCY: &str = "execution_rpc";
+
+/// Methods that must not be sent more than once, as a failed response does not mean that the
+/// request had no effect
+const NON_IDEMPOTENT_METHODS: &[&str] = &[
+    "eth_sendRawTransaction",
+    "eth_sendTransaction",
+    "eth_sendRawTransactionConditional",
+];
+
+/// A transport that sends each request to the first healthy execution endpoint, failing over to
+/// the next one on transport errors, and that requires a quorum of matching responses for the
+/// configured methods
+#[derive(Debug, Clone)]
+pub struct FailoverTransport {
+    endpoints: Arc<[Endpoint]>,
+    failover: FailoverConfig,
+    quorum: Option<QuorumConfig>,
+    retry: RetryConfig,
+    dependency: &'static str,
+}
+
+#[derive(Debug, Clone)]
+struct Endpoint {
+    transport: BoxTransport,
+    health: EndpointHealth,
+    rate_limiter: Option<TokenBucket>,
+}
+
+impl Endpoint {
+    async fn call(&self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+
+        let method = match &request {
+            RequestPacket::Single(single) => single.method().to_owned(),
+            RequestPacket::Batch(_) => "batch".to_owned(),
+        };
+
+        let start = Instant::now();
+        let response = self.transport.clone().call(request).await;
+        metrics().record_rpc_call("execution", &method, response.is_ok(), start.elapsed());
+
+        response
+    }
+}
+
+impl FailoverTransport {
+    /// Connect to all `endpoints`.
+    /// # Errors
+    /// Returns an error if there are no endpoints, if any of them cannot be connected to or if
+    /// the quorum cannot be reached with the number of endpoints.
+    pub async fn connect(
+        endpoints: &Endpoints,
+        failover: FailoverConfig,
+        quorum: Option<QuorumConfig>,
+    ) -> Result<Self, TransportError> {
+        let urls = endpoints.urls();
+        if urls.is_empty() {
+            return Err(TransportErrorKind::custom_str(
+                "at least one execution rpc endpoint is required",
+            ));
+        }
+
+        if let Some(quorum) = &quorum {
+            quorum
+                .validate(urls.len())
+                .map_err(|err| TransportErrorKind::custom_str(&err))?;
+        }
+
+        let mut connected = Vec::with_capacity(urls.len());
+        for url in urls {
+            let transport = BuiltInConnectionString::from_str(url)?
+                .connect_boxed()
+                .await?;
+            connected.push(Endpoint {
+                transport,
+                health: EndpointHealth::new(url.clone()),
+                rate_limiter: None,
+            });
+        }
+
+        Ok(Self {
+            endpoints: connected.into(),
+            failover,
+            quorum,
+            retry: RetryConfig::default(),
+            dependency: EXECUTION_RPC_DEPENDENCY,
+        })
+    }
+
+    /// Report the health of the endpoints as `dependency` rather than as
+    /// [`EXECUTION_RPC_DEPENDENCY`], for modules talking to more than one execution chain.
+    #[must_use]
+    pub fn with_dependency(mut self, dependency: &'static str) -> Self {
+        self.dependency = dependency;
+        self
+    }
+
+    /// Retry failed requests according to `retry`.
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Limit the request rate of each endpoint, if `rate_limit` is set.
+    #[must_use]
+    pub fn with_rate_limit(mut self, rate_limit: Option<&RateLimitConfig>) -> Self {
+        self.endpoints = self
+            .endpoints
+            .iter()
+            .map(|endpoint| Endpoint {
+                rate_limiter: rate_limit.map(TokenBucket::new),
+                ..endpoint.clone()
+            })
+            .collect();
+        self
+    }
+
+    /// The health of each endpoint, in order of preference.
+    pub fn health(&self) -> impl Iterator<Item = &EndpointHealth> {
+        self.endpoints.iter().map(|endpoint| &endpoint.health)
+    }
+
+    /// Wrap the transport into an RPC client, to be used with `ProviderBuilder::on_client`.
+    #[must_use]
+    pub fn into_client(self) -> RpcClient<BoxTransport> {
+        RpcClient::new(BoxTransport::new(self), false)
+    }
+
+    async fn request(self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        let response = if is_idempotent(&request) {
+            retry(&self.retry, is_retryable, || {
+                self.request_once(request.clone())
+            })
+            .await
+        } else {
+            self.request_single_endpoint(request).await
+        };
+        ibc_eureka_telemetry::health::health().record(self.dependency, &response);
+
+        response
+    }
+
+    async fn request_once(&self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        match (&self.quorum, &request) {
+            (Some(quorum), RequestPacket::Single(single))
+                if quorum
+                    .methods
+                    .iter()
+                    .any(|method| method == single.method()) =>
+            {
+                self.request_quorum(request, quorum.threshold).await
+            }
+            _ => self.request_failover(request).await,
+        }
+    }
+
+    async fn request_failover(
+        &self,
+        request: RequestPacket,
+    ) -> Result<ResponsePacket, TransportError> {
+        let mut last_err = None;
+
+        for index in attempt_order(self.health()) {
+            let endpoint = &self.endpoints[index];
+            let health = &endpoint.health;
+
+            match endpoint.call(request.clone()).await {
+                Ok(response) => {
+                    health.record_success();
+                    return Ok(response);
+                }
+                Err(err) => {
+                    warn!(endpoint = health.url(), %err, "execution rpc request failed");
+                    health.record_failure(&self.failover);
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        Err(last_err.expect("there is at least one endpoint"))
+    }
+
+    /// Send `request` to the first healthy endpoint only, without failing over to the others.
+    async fn request_single_endpoint(
+        &self,
+        request: RequestPacket,
+    ) -> Result<ResponsePacket, TransportError> {
+        let endpoint = &self.endpoints[attempt_order(self.health())[0]];
+        let health = &endpoint.health;
+
+        let response = endpoint.call(request).await;
+        match &response {
+            Ok(_) => health.record_success(),
+            Err(err) => {
+                warn!(endpoint = health.url(), %err, "execution rpc request failed");
+                health.record_failure(&self.failover);
+            }
+        }
+
+        response
+    }
+
+    async fn request_quorum(
+        &self,
+        request: RequestPacket,
+        threshold: usize,
+    ) -> Result<ResponsePacket, TransportError> {
+        let responses = join_all(self.endpoints.iter().map(|endpoint| {
+            let request = request.clone();
+            async move { (&endpoint.health, endpoint.call(request).await) }
+        }))
+        .await;
+
+        let mut groups: Vec<(Vec<PayloadKey>, ResponsePacket, usize)> = vec![];
+        for (health, response) in responses {
+            match response {
+                Ok(response) => {
+                    health.record_success();
+
+                    let key = response_key(&response);
+                    match groups
+                        .iter_mut()
+                        .find(|(group_key, _, _)| *group_key == key)
+                    {
+                        Some((_, _, count)) => *count += 1,
+                        None => groups.push((key, response, 1)),
+                    }
+                }
+                Err(err) => {
+                    warn!(endpoint = health.url(), %err, "execution rpc request failed");
+                    health.record_failure(&self.failover);
+                }
+            }
+        }
+
+        groups
+            .into_iter()
+            .find(|(_, _, count)| *count >= threshold)
+            .map(|(_, response, _)| response)
+            .ok_or_else(|| {
+                TransportErrorKind::custom_str(&format!(
+                    "no quorum of {threshold} matching responses from the execution rpc endpoints"
+                ))
+            })
+    }
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().request(request))
+    }
+}
+
+/// Whether `request` may be sent more than once, that is retried or failed over to another
+/// endpoint.
+fn is_idempotent(request: &RequestPacket) -> bool {
+    let is_idempotent = |method: &str| !NON_IDEMPOTENT_METHODS.contains(&method);
+
+    match request {
+        RequestPacket::Single(single) => is_idempotent(single.method()),
+        RequestPacket::Batch(batch) => batch.iter().all(|single| is_idempotent(single.method())),
+    }
+}
+
+/// Whether a request that failed with `err` may succeed if retried. Rate limited (HTTP 429) and
+/// server side (HTTP 5xx) errors are retried, as are connection errors and failed quorums.
+fn is_retryable(err: &TransportError) -> bool {
+    match err {
+        RpcError::Transport(TransportErrorKind::HttpError(err)) => {
+            err.status == 429 || err.status >= 500
+        }
+        RpcError::Transport(_) => true,
+        _ => false,
+    }
+}
+
+/// The result, or the code and message of the error, of a response
+type PayloadKey = Result<Value, (i64, String)>;
+
+/// A key that is equal for responses with the same result or error, regardless of their id and
+/// of how the result is formatted.
+fn response_key(response: &ResponsePacket) -> Vec<PayloadKey> {
+    fn payload_key(response: &Response) -> PayloadKey {
+        match &response.payload {
+            ResponsePayload::Success(result) => Ok(serde_json::from_str(result.get())
+                .unwrap_or_else(|_| Value::String(result.get().to_owned()))),
+            ResponsePayload::Failure(err) => Err((err.code, err.message.to_string())),
+        }
+    }
+
+    match response {
+        ResponsePacket::Single(response) => vec![payload_key(response)],
+        ResponsePacket::Batch(responses) => responses.iter().map(payload_key).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::rpc::json_rpc::{Id, Request, SerializedRequest};
+    use ibc_eureka_test_utils::stand_in::StandIn;
+    use serde_json::json;
+
+    use super::*;
+
+    fn serialized(method: &'static str) -> SerializedRequest {
+        Request::new(method, Id::Number(1), json!([]))
+            .serialize()
+            .unwrap()
+    }
+
+    fn request(method: &'static str) -> RequestPacket {
+        RequestPacket::Single(serialized(method))
+    }
+
+    async fn transport(stand_ins: &[&StandIn]) -> FailoverTransport {
+        let urls = stand_ins.iter().map(|stand_in| stand_in.url()).collect();
+
+        FailoverTransport::connect(&Endpoints::Multiple(urls), FailoverConfig::default(), None)
+            .await
+            .unwrap()
+            .with_retry(RetryConfig {
+                max_attempts: 3,
+                initial_backoff_ms: 1,
+                max_backoff_ms: 1,
+            })
+    }
+
+    fn response(raw: &str) -> ResponsePacket {
+        serde_json::from_str(raw).unwrap()
+    }
+
+    #[test]
+    fn response_key_ignores_the_id_and_the_formatting() {
+        assert_eq!(
+            response_key(&response(
+                r#"{"jsonrpc":"2.0","id":1,"result":{"number":"0x1","hash":"0xab"}}"#
+            )),
+            response_key(&response(
+                r#"{"jsonrpc":"2.0","id":2,"result":{ "hash": "0xab", "number": "0x1" }}"#
+            )),
+        );
+    }
+
+    #[test]
+    fn response_key_distinguishes_results_and_errors() {
+        let result = response(r#"{"jsonrpc":"2.0","id":1,"result":"0x1"}"#);
+        let other_result = response(r#"{"jsonrpc":"2.0","id":1,"result":"0x2"}"#);
+        let error = response(
+            r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"missing trie node"}}"#,
+        );
+
+        assert_ne!(response_key(&result), response_key(&other_result));
+        assert_ne!(response_key(&result), response_key(&error));
+        assert_eq!(
+            response_key(&error),
+            vec![Err((-32000, "missing trie node".to_string()))]
+        );
+    }
+
+    #[test]
+    fn response_key_compares_batches_in_order() {
+        let batch = response(
+            r#"[{"jsonrpc":"2.0","id":1,"result":"0x1"},{"jsonrpc":"2.0","id":2,"result":"0x2"}]"#,
+        );
+        let reordered = response(
+            r#"[{"jsonrpc":"2.0","id":2,"result":"0x2"},{"jsonrpc":"2.0","id":1,"result":"0x1"}]"#,
+        );
+
+        assert_ne!(response_key(&batch), response_key(&reordered));
+    }
+
+    #[test]
+    fn quorums_default_to_proofs() {
+        let quorum: QuorumConfig = serde_json::from_value(json!({ "threshold": 2 })).unwrap();
+
+        assert_eq!(quorum.methods, ["eth_getProof"]);
+    }
+
+    #[test]
+    fn transactions_are_not_idempotent() {
+        assert!(is_idempotent(&request("eth_getProof")));
+        assert!(!is_idempotent(&request("eth_sendRawTransaction")));
+        assert!(!is_idempotent(&RequestPacket::Batch(vec![
+            serialized("eth_blockNumber"),
+            serialized("eth_sendRawTransaction"),
+        ])));
+    }
+
+    #[tokio::test]
+    async fn transactions_are_neither_retried_nor_failed_over() {
+        let preferred = StandIn::serve(|_| (503, json!({}))).await;
+        let fallback = StandIn::serve(|_| (503, json!({}))).await;
+        let transport = transport(&[&preferred, &fallback]).await;
+
+        transport
+            .clone()
+            .request(request("eth_sendRawTransaction"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(preferred.requests().len(), 1);
+        assert_eq!(fallback.requests().len(), 0);
+
+        transport
+            .request(request("eth_blockNumber"))
+            .await
+            .unwrap_err();
+
+        assert_eq!(preferred.requests().len(), 4);
+        assert_eq!(fallback.requests().len(), 3);
+    }
+}
@@ -0,0 +1,99 @@
+//! Health tracking of RPC endpoints
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::config::FailoverConfig;
+
+/// The health of a single RPC endpoint, shared between all clones
+#[derive(Debug, Clone)]
+pub struct EndpointHealth {
+    url: String,
+    state: Arc<Mutex<HealthState>>,
+}
+
+#[derive(Debug, Default)]
+struct HealthState {
+    consecutive_failures: u32,
+    unhealthy_until: Option<Instant>,
+    last_success: Option<Instant>,
+}
+
+impl EndpointHealth {
+    /// Create the health tracker of a new, healthy endpoint.
+    #[must_use]
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            state: Arc::default(),
+        }
+    }
+
+    /// The URL of the endpoint.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Whether the endpoint is healthy, i.e. not cooling down after repeated failures.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.state
+            .lock()
+            .expect("lock is poisoned")
+            .unhealthy_until
+            .map_or(true, |unhealthy_until| Instant::now() >= unhealthy_until)
+    }
+
+    /// When the endpoint last responded successfully, if ever.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    #[must_use]
+    pub fn last_success(&self) -> Option<Instant> {
+        self.state.lock().expect("lock is poisoned").last_success
+    }
+
+    /// Record a successful request.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().expect("lock is poisoned");
+        state.consecutive_failures = 0;
+        state.unhealthy_until = None;
+        state.last_success = Some(Instant::now());
+    }
+
+    /// Record a failed request, marking the endpoint unhealthy after too many consecutive
+    /// failures.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub fn record_failure(&self, config: &FailoverConfig) {
+        let mut state = self.state.lock().expect("lock is poisoned");
+        state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+
+        if state.consecutive_failures >= config.max_consecutive_failures {
+            state.unhealthy_until =
+                Some(Instant::now() + Duration::from_secs(config.cooldown_secs));
+        }
+    }
+}
+
+/// The order to try endpoints in: healthy endpoints first, then unhealthy ones as a last resort,
+/// both in order of preference.
+#[must_use]
+pub fn attempt_order<'a>(endpoints: impl IntoIterator<Item = &'a EndpointHealth>) -> Vec<usize> {
+    let (healthy, unhealthy): (Vec<_>, Vec<_>) = endpoints
+        .into_iter()
+        .enumerate()
+        .partition(|(_, health)| health.is_healthy());
+
+    healthy
+        .into_iter()
+        .chain(unhealthy)
+        .map(|(index, _)| index)
+        .collect()
+}
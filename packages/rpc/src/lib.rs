@@ -0,0 +1,9 @@
+//! # RPC Endpoint Utilities for IBC Eureka Voyager Modules
+
+#![deny(clippy::nursery, clippy::pedantic, warnings, missing_docs)]
+
+pub mod beacon;
+pub mod config;
+pub mod execution;
+pub mod health;
+pub mod retry;
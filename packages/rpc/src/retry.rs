@@ -0,0 +1,267 @@
+//! Retries with exponential backoff, and per-endpoint rate limiting
+
+use std::{
+    future::Future,
+    num::NonZeroU32,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// How failed requests are retried
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, default)]
+pub struct RetryConfig {
+    /// The maximum number of attempts per request, including the first one.
+    pub max_attempts: u32,
+    /// The backoff before the first retry, in milliseconds. The backoff doubles after every
+    /// attempt.
+    pub initial_backoff_ms: u64,
+    /// The maximum backoff between two attempts, in milliseconds.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 250,
+            max_backoff_ms: 10_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// The backoff after the `attempt`th failed attempt (starting at 1), with full jitter.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(rand::thread_rng().gen_range(0..=self.backoff_cap_ms(attempt)))
+    }
+
+    /// The upper bound of the backoff after the `attempt`th failed attempt, in milliseconds.
+    fn backoff_cap_ms(&self, attempt: u32) -> u64 {
+        self.initial_backoff_ms
+            .saturating_mul(
+                1_u64
+                    .checked_shl(attempt.saturating_sub(1))
+                    .unwrap_or(u64::MAX),
+            )
+            .min(self.max_backoff_ms)
+    }
+}
+
+/// Run `request` until it succeeds, it fails with an error that is not retryable, or the
+/// maximum number of attempts is reached.
+/// # Errors
+/// Returns the error of the last attempt.
+pub async fn retry<T, E, F, Fut>(
+    config: &RetryConfig,
+    is_retryable: impl Fn(&E) -> bool,
+    mut request: F,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut attempt = 1;
+
+    loop {
+        match request().await {
+            Err(err) if attempt < config.max_attempts && is_retryable(&err) => {
+                let backoff = config.backoff(attempt);
+                debug!(attempt, ?backoff, "request failed, retrying");
+
+                tokio::time::sleep(backoff).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// The rate limit of a single endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// The sustained number of requests per second.
+    pub requests_per_second: NonZeroU32,
+    /// The number of requests that can be sent in a burst. Defaults to `requests_per_second`.
+    #[serde(default)]
+    pub burst: Option<NonZeroU32>,
+}
+
+/// A token bucket rate limiter, shared between all clones
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: f64,
+    refill_per_sec: f64,
+    state: Arc<Mutex<BucketState>>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a new, full token bucket.
+    #[must_use]
+    pub fn new(config: &RateLimitConfig) -> Self {
+        let capacity = f64::from(config.burst.unwrap_or(config.requests_per_second).get());
+
+        Self {
+            capacity,
+            refill_per_sec: f64::from(config.requests_per_second.get()),
+            state: Arc::new(Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// Wait until a token is available, and take it.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().expect("lock is poisoned");
+
+                let now = Instant::now();
+                let refilled =
+                    now.duration_since(state.last_refill).as_secs_f64() * self.refill_per_sec;
+                state.tokens = (state.tokens + refilled).min(self.capacity);
+                state.last_refill = now;
+
+                if state.tokens >= 1.0 {
+                    state.tokens -= 1.0;
+                    return;
+                }
+
+                Duration::from_secs_f64((1.0 - state.tokens) / self.refill_per_sec)
+            };
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn fast_retry(max_attempts: u32) -> RetryConfig {
+        RetryConfig {
+            max_attempts,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+        }
+    }
+
+    /// Run `retry` with a request that fails `failures` times with `err`, and return the result
+    /// and the number of attempts.
+    async fn run(
+        config: &RetryConfig,
+        failures: u32,
+        err: &'static str,
+    ) -> (Result<(), &'static str>, u32) {
+        let attempts = AtomicU32::new(0);
+
+        let result = retry(
+            config,
+            |err| *err == "retryable",
+            || async {
+                if attempts.fetch_add(1, Ordering::SeqCst) < failures {
+                    Err(err)
+                } else {
+                    Ok(())
+                }
+            },
+        )
+        .await;
+
+        (result, attempts.load(Ordering::SeqCst))
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let config = RetryConfig::default();
+
+        assert_eq!(
+            (1..=7)
+                .map(|attempt| config.backoff_cap_ms(attempt))
+                .collect::<Vec<_>>(),
+            [250, 500, 1_000, 2_000, 4_000, 8_000, 10_000]
+        );
+        assert_eq!(config.backoff_cap_ms(64), 10_000);
+        assert_eq!(config.backoff_cap_ms(u32::MAX), 10_000);
+    }
+
+    #[test]
+    fn backoff_is_jittered_below_the_cap() {
+        let config = RetryConfig::default();
+
+        for attempt in 1..=10 {
+            for _ in 0..100 {
+                assert!(
+                    config.backoff(attempt)
+                        <= Duration::from_millis(config.backoff_cap_ms(attempt))
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_stop_after_max_attempts() {
+        assert_eq!(
+            run(&fast_retry(3), u32::MAX, "retryable").await,
+            (Err("retryable"), 3)
+        );
+        assert_eq!(
+            run(&fast_retry(1), u32::MAX, "retryable").await,
+            (Err("retryable"), 1)
+        );
+        assert_eq!(run(&fast_retry(3), 2, "retryable").await, (Ok(()), 3));
+    }
+
+    #[tokio::test]
+    async fn errors_that_are_not_retryable_are_returned_immediately() {
+        assert_eq!(
+            run(&fast_retry(3), u32::MAX, "fatal").await,
+            (Err("fatal"), 1)
+        );
+    }
+
+    #[tokio::test]
+    async fn token_bucket_limits_the_rate_after_a_burst() {
+        let bucket = TokenBucket::new(&RateLimitConfig {
+            requests_per_second: NonZeroU32::new(10).unwrap(),
+            burst: NonZeroU32::new(2),
+        });
+
+        let start = Instant::now();
+        bucket.acquire().await;
+        bucket.clone().acquire().await;
+        assert!(start.elapsed() < Duration::from_millis(50));
+
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn token_bucket_burst_defaults_to_the_rate() {
+        let bucket = TokenBucket::new(&RateLimitConfig {
+            requests_per_second: NonZeroU32::new(5).unwrap(),
+            burst: None,
+        });
+
+        assert!((bucket.capacity - 5.0).abs() < f64::EPSILON);
+        assert!((bucket.refill_per_sec - 5.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,206 @@
+//! Health and readiness of the upstream dependencies of a module
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::future::join_all;
+use serde::Serialize;
+
+type CheckFuture = Pin<Box<dyn Future<Output = Result<Option<u64>, String>> + Send>>;
+type Check = Arc<dyn Fn() -> CheckFuture + Send + Sync>;
+
+/// The status of a dependency
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyStatus {
+    /// The dependency has not been called or checked yet
+    #[default]
+    Unknown,
+    /// The last call or check succeeded
+    Healthy,
+    /// The last call or check failed
+    Unhealthy,
+}
+
+/// The health of a single dependency
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct DependencyHealth {
+    /// The status of the dependency
+    pub status: DependencyStatus,
+    /// The time of the last successful call, in seconds since the unix epoch
+    pub last_success: Option<u64>,
+    /// The error of the last failed call, if the dependency is unhealthy
+    pub last_error: Option<String>,
+    /// The latest height reported by the dependency, if it reports one
+    pub latest_height: Option<u64>,
+}
+
+/// The health of a module and of all its dependencies
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct HealthReport {
+    /// Whether no dependency is unhealthy
+    pub ready: bool,
+    /// The health of each dependency, by name
+    pub dependencies: BTreeMap<String, DependencyHealth>,
+    /// The latest heights served by the module, by chain ID
+    pub latest_heights: BTreeMap<String, u64>,
+}
+
+/// Tracks the health of the dependencies of this process. Calls made through the module record
+/// their outcome here, and registered checks actively probe the dependencies on readiness
+/// requests.
+#[derive(Default)]
+pub struct Health {
+    dependencies: Mutex<BTreeMap<String, DependencyHealth>>,
+    latest_heights: Mutex<BTreeMap<String, u64>>,
+    checks: Mutex<Vec<(String, Check)>>,
+}
+
+impl std::fmt::Debug for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Health")
+            .field("dependencies", &self.dependencies)
+            .field("latest_heights", &self.latest_heights)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The health of this process.
+#[must_use]
+pub fn health() -> &'static Health {
+    static HEALTH: OnceLock<Health> = OnceLock::new();
+
+    HEALTH.get_or_init(Health::default)
+}
+
+impl Health {
+    /// Register a check that probes `dependency`, returning its latest height if it has one.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub fn register_check<F, Fut>(&self, dependency: impl Into<String>, check: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Option<u64>, String>> + Send + 'static,
+    {
+        let dependency = dependency.into();
+
+        self.dependencies
+            .lock()
+            .expect("lock is poisoned")
+            .entry(dependency.clone())
+            .or_default();
+        self.checks.lock().expect("lock is poisoned").push((
+            dependency,
+            Arc::new(move || -> CheckFuture { Box::pin(check()) }),
+        ));
+    }
+
+    /// Record a successful call to `dependency`.
+    pub fn record_success(&self, dependency: &str) {
+        self.update(dependency, |health| {
+            health.status = DependencyStatus::Healthy;
+            health.last_success = Some(unix_now());
+            health.last_error = None;
+        });
+    }
+
+    /// Record a failed call to `dependency`.
+    pub fn record_failure(&self, dependency: &str, err: impl Display) {
+        self.update(dependency, |health| {
+            health.status = DependencyStatus::Unhealthy;
+            health.last_error = Some(err.to_string());
+        });
+    }
+
+    /// Record the outcome of a call to `dependency`.
+    pub fn record<T, E: Display>(&self, dependency: &str, result: &Result<T, E>) {
+        match result {
+            Ok(_) => self.record_success(dependency),
+            Err(err) => self.record_failure(dependency, err),
+        }
+    }
+
+    /// Record the latest height served by the module for `chain_id`.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub fn record_latest_height(&self, chain_id: &str, height: u64) {
+        self.latest_heights
+            .lock()
+            .expect("lock is poisoned")
+            .insert(chain_id.to_owned(), height);
+    }
+
+    /// The health of all dependencies, as of their last call or check.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    #[must_use]
+    pub fn report(&self) -> HealthReport {
+        let dependencies = self.dependencies.lock().expect("lock is poisoned").clone();
+
+        HealthReport {
+            ready: dependencies
+                .values()
+                .all(|health| health.status != DependencyStatus::Unhealthy),
+            dependencies,
+            latest_heights: self
+                .latest_heights
+                .lock()
+                .expect("lock is poisoned")
+                .clone(),
+        }
+    }
+
+    /// Run all registered checks concurrently, each bounded by `timeout`, and report the health
+    /// of all dependencies.
+    /// # Panics
+    /// Panics if the lock is poisoned.
+    pub async fn check(&self, timeout: Duration) -> HealthReport {
+        let checks = self.checks.lock().expect("lock is poisoned").clone();
+
+        let results = join_all(checks.into_iter().map(|(dependency, check)| async move {
+            let result = tokio::time::timeout(timeout, check())
+                .await
+                .unwrap_or_else(|_| Err(format!("check timed out after {timeout:?}")));
+
+            (dependency, result)
+        }))
+        .await;
+
+        for (dependency, result) in results {
+            match result {
+                Ok(latest_height) => {
+                    self.record_success(&dependency);
+                    if latest_height.is_some() {
+                        self.update(&dependency, |health| health.latest_height = latest_height);
+                    }
+                }
+                Err(err) => self.record_failure(&dependency, err),
+            }
+        }
+
+        self.report()
+    }
+
+    fn update(&self, dependency: &str, f: impl FnOnce(&mut DependencyHealth)) {
+        f(self
+            .dependencies
+            .lock()
+            .expect("lock is poisoned")
+            .entry(dependency.to_owned())
+            .or_default());
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
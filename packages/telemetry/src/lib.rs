@@ -0,0 +1,7 @@
+//! # Telemetry for IBC Eureka Voyager Modules
+
+#![deny(clippy::nursery, clippy::pedantic, warnings, missing_docs)]
+
+pub mod health;
+pub mod metrics;
+pub mod server;
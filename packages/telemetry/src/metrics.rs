@@ -0,0 +1,332 @@
+//! Prometheus metrics shared by all modules
+
+use std::{sync::OnceLock, time::Duration};
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+/// The namespace of all metrics
+const NAMESPACE: &str = "ibc_eureka";
+
+/// The buckets of the proof generation time histogram, in seconds
+const PROOF_GENERATION_BUCKETS: &[f64] = &[
+    1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1200.0, 1800.0,
+];
+
+/// The metrics recorded by the modules
+#[derive(Debug)]
+pub struct Metrics {
+    registry: Registry,
+    /// RPC calls, by endpoint kind, method and status
+    pub rpc_calls: IntCounterVec,
+    /// RPC call duration, by endpoint kind and method
+    pub rpc_call_duration: HistogramVec,
+    /// Errors returned by the modules, by module and error kind
+    pub errors: IntCounterVec,
+    /// Proof generation time, by module and proof type
+    pub proof_generation_duration: HistogramVec,
+    /// Submitted transactions, by module and status
+    pub tx_submissions: IntCounterVec,
+    /// Gas used by submitted transactions, by module
+    pub gas_used: IntCounterVec,
+    /// The latest height observed, by module and chain ID
+    pub latest_height: IntGaugeVec,
+}
+
+/// The metrics of this process.
+#[must_use]
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+    METRICS.get_or_init(Metrics::new)
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Self {
+        let registry = Registry::new();
+
+        let rpc_calls = IntCounterVec::new(
+            Opts::new("rpc_calls_total", "RPC calls made to upstream endpoints")
+                .namespace(NAMESPACE),
+            &["endpoint_kind", "method", "status"],
+        )
+        .expect("metric is valid");
+        let rpc_call_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "rpc_call_duration_seconds",
+                "Duration of RPC calls made to upstream endpoints",
+            )
+            .namespace(NAMESPACE),
+            &["endpoint_kind", "method"],
+        )
+        .expect("metric is valid");
+        let errors = IntCounterVec::new(
+            Opts::new("errors_total", "Errors returned by the module").namespace(NAMESPACE),
+            &["module", "kind"],
+        )
+        .expect("metric is valid");
+        let proof_generation_duration = HistogramVec::new(
+            HistogramOpts::new("proof_generation_seconds", "Duration of proof generation")
+                .namespace(NAMESPACE)
+                .buckets(PROOF_GENERATION_BUCKETS.to_vec()),
+            &["module", "proof_type"],
+        )
+        .expect("metric is valid");
+        let tx_submissions = IntCounterVec::new(
+            Opts::new("tx_submissions_total", "Submitted transactions").namespace(NAMESPACE),
+            &["module", "status"],
+        )
+        .expect("metric is valid");
+        let gas_used = IntCounterVec::new(
+            Opts::new("gas_used_total", "Gas used by submitted transactions").namespace(NAMESPACE),
+            &["module"],
+        )
+        .expect("metric is valid");
+        let latest_height = IntGaugeVec::new(
+            Opts::new("latest_height", "The latest height observed").namespace(NAMESPACE),
+            &["module", "chain_id"],
+        )
+        .expect("metric is valid");
+
+        for collector in [
+            Box::new(rpc_calls.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(rpc_call_duration.clone()),
+            Box::new(errors.clone()),
+            Box::new(proof_generation_duration.clone()),
+            Box::new(tx_submissions.clone()),
+            Box::new(gas_used.clone()),
+            Box::new(latest_height.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metrics are only registered once");
+        }
+
+        Self {
+            registry,
+            rpc_calls,
+            rpc_call_duration,
+            errors,
+            proof_generation_duration,
+            tx_submissions,
+            gas_used,
+            latest_height,
+        }
+    }
+
+    /// Record an RPC call to an upstream endpoint.
+    pub fn record_rpc_call(
+        &self,
+        endpoint_kind: &str,
+        method: &str,
+        success: bool,
+        duration: Duration,
+    ) {
+        let status = if success { "ok" } else { "error" };
+
+        self.rpc_calls
+            .with_label_values(&[endpoint_kind, method, status])
+            .inc();
+        self.rpc_call_duration
+            .with_label_values(&[endpoint_kind, method])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Record an error returned by a module.
+    pub fn record_error(&self, module: &str, kind: &str) {
+        self.errors.with_label_values(&[module, kind]).inc();
+    }
+
+    /// Record the time it took to generate a proof.
+    pub fn record_proof_generation(&self, module: &str, proof_type: &str, duration: Duration) {
+        self.proof_generation_duration
+            .with_label_values(&[module, proof_type])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Record a submitted transaction, and the gas it used if it was included.
+    pub fn record_tx_submission(&self, module: &str, status: &str, gas_used: Option<u64>) {
+        self.tx_submissions
+            .with_label_values(&[module, status])
+            .inc();
+
+        if let Some(gas_used) = gas_used {
+            self.gas_used.with_label_values(&[module]).inc_by(gas_used);
+        }
+    }
+
+    /// Record the latest height observed on a chain.
+    pub fn record_latest_height(&self, module: &str, chain_id: &str, height: u64) {
+        self.latest_height
+            .with_label_values(&[module, chain_id])
+            .set(height.try_into().unwrap_or(i64::MAX));
+    }
+
+    /// Encode all metrics in the Prometheus text format.
+    /// # Panics
+    /// Panics if the metrics cannot be encoded, which does not happen for the text format.
+    #[must_use]
+    pub fn encode(&self) -> String {
+        let mut buffer = vec![];
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("encoding into a vec is infallible");
+
+        String::from_utf8(buffer).expect("the text format is valid utf-8")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The names of the metric families with at least one sample, and their label names.
+    fn families(metrics: &Metrics) -> Vec<(String, Vec<String>)> {
+        metrics
+            .registry
+            .gather()
+            .iter()
+            .map(|family| {
+                let labels = family.get_metric()[0]
+                    .get_label()
+                    .iter()
+                    .map(|label| label.get_name().to_owned())
+                    .collect();
+
+                (family.get_name().to_owned(), labels)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn metrics_are_registered_with_their_labels() {
+        let metrics = Metrics::new();
+        metrics.record_rpc_call("execution", "eth_getProof", true, Duration::from_millis(10));
+        metrics.record_error("eth-eureka", "no_finalized_height");
+        metrics.record_proof_generation("sp1-ics07", "update_client", Duration::from_secs(30));
+        metrics.record_tx_submission("eth-eureka", "included", Some(21_000));
+        metrics.record_latest_height("eth-eureka", "1", 100);
+
+        let labels = |labels: &[&str]| labels.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            families(&metrics),
+            [
+                (
+                    "ibc_eureka_errors_total".to_owned(),
+                    labels(&["kind", "module"])
+                ),
+                ("ibc_eureka_gas_used_total".to_owned(), labels(&["module"])),
+                (
+                    "ibc_eureka_latest_height".to_owned(),
+                    labels(&["chain_id", "module"])
+                ),
+                (
+                    "ibc_eureka_proof_generation_seconds".to_owned(),
+                    labels(&["module", "proof_type"])
+                ),
+                (
+                    "ibc_eureka_rpc_call_duration_seconds".to_owned(),
+                    labels(&["endpoint_kind", "method"])
+                ),
+                (
+                    "ibc_eureka_rpc_calls_total".to_owned(),
+                    labels(&["endpoint_kind", "method", "status"])
+                ),
+                (
+                    "ibc_eureka_tx_submissions_total".to_owned(),
+                    labels(&["module", "status"])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_are_counted_per_module() {
+        let metrics = Metrics::new();
+        metrics.record_error("op-eureka", "no_finalized_height");
+
+        let errors = |module| {
+            metrics
+                .errors
+                .with_label_values(&[module, "no_finalized_height"])
+                .get()
+        };
+        assert_eq!(errors("op-eureka"), 1);
+        assert_eq!(errors("eth-eureka"), 0);
+    }
+
+    #[test]
+    fn rpc_calls_are_counted_by_status() {
+        let metrics = Metrics::new();
+        metrics.record_rpc_call("beacon", "block", true, Duration::from_millis(10));
+        metrics.record_rpc_call("beacon", "block", false, Duration::from_millis(20));
+        metrics.record_rpc_call("beacon", "block", false, Duration::from_millis(30));
+
+        let calls = |status| {
+            metrics
+                .rpc_calls
+                .with_label_values(&["beacon", "block", status])
+                .get()
+        };
+        assert_eq!(calls("ok"), 1);
+        assert_eq!(calls("error"), 2);
+        assert_eq!(
+            metrics
+                .rpc_call_duration
+                .with_label_values(&["beacon", "block"])
+                .get_sample_count(),
+            3
+        );
+    }
+
+    #[test]
+    fn gas_is_only_recorded_for_included_transactions() {
+        let metrics = Metrics::new();
+        metrics.record_tx_submission("eth-eureka", "included", Some(21_000));
+        metrics.record_tx_submission("eth-eureka", "failed", None);
+
+        assert_eq!(
+            metrics
+                .tx_submissions
+                .with_label_values(&["eth-eureka", "failed"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            metrics.gas_used.with_label_values(&["eth-eureka"]).get(),
+            21_000
+        );
+    }
+
+    #[test]
+    fn latest_heights_saturate() {
+        let metrics = Metrics::new();
+        metrics.record_latest_height("eth-eureka", "1", u64::MAX);
+
+        assert_eq!(
+            metrics
+                .latest_height
+                .with_label_values(&["eth-eureka", "1"])
+                .get(),
+            i64::MAX
+        );
+    }
+
+    #[test]
+    fn metrics_are_encoded_in_the_text_format() {
+        let metrics = Metrics::new();
+        metrics.record_error("op-eureka", "no_finalized_height");
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains("# TYPE ibc_eureka_errors_total counter"));
+        assert!(encoded.contains(
+            r#"ibc_eureka_errors_total{kind="no_finalized_height",module="op-eureka"} 1"#
+        ));
+    }
+
+    #[test]
+    fn metrics_are_shared_by_the_process() {
+        assert!(std::ptr::eq(metrics(), metrics()));
+    }
+}
@@ -0,0 +1,262 @@
+//! A minimal HTTP server exposing the metrics and the health of the module
+
+use std::{convert::Infallible, net::SocketAddr, time::Duration};
+
+use http_body_util::Full;
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{HeaderValue, CONTENT_TYPE},
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tracing::{debug, info, warn};
+
+use crate::{
+    health::{health, Health, HealthReport},
+    metrics::{metrics, Metrics},
+};
+
+/// The timeout of each dependency check run on `GET /ready`
+const READINESS_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The configuration of the telemetry endpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[allow(clippy::module_name_repetitions)]
+pub struct TelemetryConfig {
+    /// The address to serve the telemetry endpoint on.
+    ///
+    /// - `GET /metrics` serves the Prometheus metrics.
+    /// - `GET /health` reports the health of the dependencies as of their last call, and always
+    ///   succeeds while the module is running (liveness).
+    /// - `GET /ready` checks every dependency, and fails if any of them is unhealthy
+    ///   (readiness).
+    pub listen_address: SocketAddr,
+}
+
+/// Bind to the configured address and serve the telemetry endpoint in the background.
+/// # Errors
+/// Returns an error if the address cannot be bound to.
+pub async fn spawn(config: &TelemetryConfig) -> std::io::Result<()> {
+    let listener = TcpListener::bind(config.listen_address).await?;
+    info!(listen_address = %config.listen_address, "serving telemetry");
+
+    tokio::spawn(serve(listener, health(), metrics()));
+
+    Ok(())
+}
+
+async fn serve(listener: TcpListener, health: &'static Health, metrics: &'static Metrics) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let service = service_fn(move |request: Request<Incoming>| async move {
+                    Ok::<_, Infallible>(
+                        respond(request.method(), request.uri().path(), health, metrics).await,
+                    )
+                });
+
+                tokio::spawn(async move {
+                    if let Err(err) = http1::Builder::new()
+                        .serve_connection(TokioIo::new(stream), service)
+                        .await
+                    {
+                        debug!(%err, "error serving telemetry request");
+                    }
+                });
+            }
+            Err(err) => warn!(%err, "error accepting telemetry connection"),
+        }
+    }
+}
+
+async fn respond(
+    method: &Method,
+    path: &str,
+    health: &Health,
+    metrics: &Metrics,
+) -> Response<Full<Bytes>> {
+    if method != Method::GET {
+        return response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "text/plain",
+            "method not allowed\n".to_owned(),
+        );
+    }
+
+    match path {
+        "/metrics" => response(
+            StatusCode::OK,
+            "text/plain; version=0.0.4",
+            metrics.encode(),
+        ),
+        "/health" => json(StatusCode::OK, &health.report()),
+        "/ready" => {
+            let report = health.check(READINESS_CHECK_TIMEOUT).await;
+            let status = if report.ready {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            };
+
+            json(status, &report)
+        }
+        _ => response(
+            StatusCode::NOT_FOUND,
+            "text/plain",
+            "not found\n".to_owned(),
+        ),
+    }
+}
+
+fn json(status: StatusCode, report: &HealthReport) -> Response<Full<Bytes>> {
+    response(
+        status,
+        "application/json",
+        serde_json::to_string(report).expect("the health report is serializable"),
+    )
+}
+
+fn response(status: StatusCode, content_type: &'static str, body: String) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt;
+    use serde_json::Value;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    async fn get(path: &str, health: &Health, metrics: &Metrics) -> (StatusCode, String, String) {
+        let response = respond(&Method::GET, path, health, metrics).await;
+
+        let content_type = response.headers()[CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let status = response.status();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+
+        (
+            status,
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn metrics_are_served_in_the_text_format() {
+        let metrics = Metrics::new();
+        metrics.record_error("eth-eureka", "fatal");
+
+        let (status, content_type, body) = get("/metrics", &Health::default(), &metrics).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "text/plain; version=0.0.4");
+        assert!(body.contains(r#"ibc_eureka_errors_total{kind="fatal",module="eth-eureka"} 1"#));
+    }
+
+    #[tokio::test]
+    async fn health_is_reported_even_if_a_dependency_is_unhealthy() {
+        let health = Health::default();
+        health.record_failure("execution_rpc", "connection refused");
+
+        let (status, content_type, body) = get("/health", &health, &Metrics::new()).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/json");
+
+        let report: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["ready"], false);
+        assert_eq!(
+            report["dependencies"]["execution_rpc"]["last_error"],
+            "connection refused"
+        );
+    }
+
+    #[tokio::test]
+    async fn readiness_fails_if_a_check_fails() {
+        let health = Health::default();
+        health.register_check("beacon_api", || async { Ok(Some(100)) });
+
+        let (status, _, body) = get("/ready", &health, &Metrics::new()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let report: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["dependencies"]["beacon_api"]["latest_height"], 100);
+
+        health.register_check("execution_rpc", || async { Err("unreachable".to_owned()) });
+
+        let (status, _, body) = get("/ready", &health, &Metrics::new()).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let report: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(report["ready"], false);
+        assert_eq!(
+            report["dependencies"]["execution_rpc"]["status"],
+            "unhealthy"
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_and_methods_are_rejected() {
+        let (health, metrics) = (Health::default(), Metrics::new());
+
+        assert_eq!(get("/", &health, &metrics).await.0, StatusCode::NOT_FOUND);
+        assert_eq!(
+            get("/metrics/all", &health, &metrics).await.0,
+            StatusCode::NOT_FOUND
+        );
+
+        assert_eq!(
+            respond(&Method::POST, "/metrics", &health, &metrics)
+                .await
+                .status(),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+    }
+
+    #[tokio::test]
+    async fn routes_are_served_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(
+            listener,
+            Box::leak(Box::default()),
+            Box::leak(Box::new(Metrics::new())),
+        ));
+
+        for (path, status_line) in [
+            ("/health", "HTTP/1.1 200 OK"),
+            ("/ready", "HTTP/1.1 200 OK"),
+            ("/metrics?format=text", "HTTP/1.1 200 OK"),
+            ("/unknown", "HTTP/1.1 404 Not Found"),
+        ] {
+            let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+            stream
+                .write_all(
+                    format!("GET {path} HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+                        .as_bytes(),
+                )
+                .await
+                .unwrap();
+
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+
+            assert!(response.starts_with(status_line), "{path}: {response}");
+        }
+    }
+}
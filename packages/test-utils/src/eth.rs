@@ -0,0 +1,80 @@
+//! Stand-ins for Ethereum execution JSON-RPC endpoints
+
+use alloy::{hex, primitives::Address};
+use ibc_eureka_rpc::retry::RetryConfig;
+use serde_json::{json, Value};
+
+use crate::stand_in::StandIn;
+
+/// A JSON-RPC error returned by a stand-in, as its code and message
+pub type JsonRpcError = (i64, String);
+
+impl StandIn {
+    /// Serve an execution JSON-RPC endpoint. `contracts` is called with the target, the input and
+    /// the block of each `eth_call`, and returns its output. The other methods are answered by
+    /// `methods`.
+    pub async fn eth_json_rpc(
+        methods: impl Fn(&str, &Value) -> Result<Value, JsonRpcError> + Send + Sync + 'static,
+        contracts: impl Fn(Address, &[u8], &Value) -> Result<Vec<u8>, JsonRpcError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self::json_rpc(move |method, params| match method {
+            "eth_call" => {
+                let (to, input) = eth_call_input(params);
+
+                contracts(to, &input, &params[1]).map(|output| json!(hex::encode_prefixed(output)))
+            }
+            _ => methods(method, params),
+        })
+        .await
+    }
+}
+
+/// A retry config without backoff, for stand-ins to be retried quickly.
+#[must_use]
+pub const fn fast_retry(max_attempts: u32) -> RetryConfig {
+    RetryConfig {
+        max_attempts,
+        initial_backoff_ms: 1,
+        max_backoff_ms: 1,
+    }
+}
+
+/// The error of a JSON-RPC method that a stand-in does not serve.
+#[must_use]
+pub fn method_not_found(method: &str) -> JsonRpcError {
+    (-32_601, format!("method `{method}` not found"))
+}
+
+/// The target and the input of an `eth_call`.
+/// # Panics
+/// Panics if the params are not those of an `eth_call`.
+#[must_use]
+pub fn eth_call_input(params: &Value) -> (Address, Vec<u8>) {
+    let call = &params[0];
+    let to = call["to"].as_str().unwrap().parse().unwrap();
+    let input = call["input"]
+        .as_str()
+        .or_else(|| call["data"].as_str())
+        .unwrap();
+
+    (to, hex::decode(input).unwrap())
+}
+
+/// The function selector of a call input.
+/// # Panics
+/// Panics if the input is shorter than a selector.
+#[must_use]
+pub fn selector(input: &[u8]) -> [u8; 4] {
+    input[..4].try_into().unwrap()
+}
+
+/// The block number an `eth_call` or `eth_getProof` was made at.
+/// # Panics
+/// Panics if the block is not a hex block number.
+#[must_use]
+pub fn block_number(block: &Value) -> u64 {
+    u64::from_str_radix(block.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+}
@@ -0,0 +1,6 @@
+//! # Test Utilities for IBC Eureka Voyager Modules
+
+#![deny(clippy::nursery, clippy::pedantic, warnings, missing_docs)]
+
+pub mod eth;
+pub mod stand_in;
@@ -0,0 +1,160 @@
+//! A local stand-in for the HTTP endpoints a module talks to, i.e. execution JSON-RPC and
+//! beacon or REST APIs
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::{Bytes, Incoming},
+    header::{HeaderValue, CONTENT_TYPE},
+    server::conn::http1,
+    service::service_fn,
+    StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+/// A request received by a [`StandIn`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// The HTTP method, e.g. `POST`
+    pub method: String,
+    /// The request path, including the query string
+    pub path: String,
+    /// The JSON body of the request, if any
+    pub body: Option<Value>,
+}
+
+/// The response of a [`StandIn`] to a request, as an HTTP status code and a JSON body
+pub type Response = (u16, Value);
+
+type Handler = dyn Fn(&Request) -> Response + Send + Sync;
+
+/// A stand-in HTTP endpoint on localhost, answering every request with a handler and recording
+/// the requests it receives
+#[derive(Clone)]
+pub struct StandIn {
+    address: SocketAddr,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl StandIn {
+    /// Serve `handler` on a random local port, until the runtime shuts down.
+    /// # Panics
+    /// Panics if no local port can be bound to.
+    pub async fn serve(handler: impl Fn(&Request) -> Response + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("a local port can be bound to");
+        let address = listener.local_addr().expect("the listener is bound");
+
+        let requests = Arc::<Mutex<Vec<Request>>>::default();
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        tokio::spawn({
+            let requests = requests.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let (handler, requests) = (handler.clone(), requests.clone());
+                    let service = service_fn(move |request| {
+                        handle(request, handler.clone(), requests.clone())
+                    });
+
+                    tokio::spawn(
+                        http1::Builder::new().serve_connection(TokioIo::new(stream), service),
+                    );
+                }
+            }
+        });
+
+        Self { address, requests }
+    }
+
+    /// Serve a JSON-RPC endpoint. `handler` is called with the method and the params of each
+    /// request, and returns either its result or its error code and message.
+    pub async fn json_rpc(
+        handler: impl Fn(&str, &Value) -> Result<Value, (i64, String)> + Send + Sync + 'static,
+    ) -> Self {
+        Self::serve(move |request| {
+            let body = request.body.clone().unwrap_or_default();
+            let id = body["id"].clone();
+            let method = body["method"].as_str().unwrap_or_default();
+
+            let response = match handler(method, &body["params"]) {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err((code, message)) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": code, "message": message },
+                }),
+            };
+
+            (200, response)
+        })
+        .await
+    }
+
+    /// The URL of the stand-in.
+    #[must_use]
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// All requests received so far, in order.
+    /// # Panics
+    /// Panics if the request log is poisoned.
+    #[must_use]
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().expect("lock is poisoned").clone()
+    }
+
+    /// The params of all JSON-RPC requests with method `method` received so far, in order.
+    #[must_use]
+    pub fn json_rpc_calls(&self, method: &str) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .filter_map(|request| request.body)
+            .filter(|body| body["method"] == method)
+            .map(|body| body["params"].clone())
+            .collect()
+    }
+}
+
+async fn handle(
+    request: hyper::Request<Incoming>,
+    handler: Arc<Handler>,
+    requests: Arc<Mutex<Vec<Request>>>,
+) -> Result<hyper::Response<Full<Bytes>>, hyper::Error> {
+    let method = request.method().to_string();
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    let body = request.into_body().collect().await?.to_bytes();
+
+    let request = Request {
+        method,
+        path,
+        body: serde_json::from_slice(&body).ok(),
+    };
+
+    requests
+        .lock()
+        .expect("lock is poisoned")
+        .push(request.clone());
+
+    let (status, body) = handler(&request);
+
+    let mut response = hyper::Response::new(Full::new(Bytes::from(body.to_string())));
+    *response.status_mut() = StatusCode::from_u16(status).expect("the status code is valid");
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    Ok(response)
+}
@@ -5,7 +5,17 @@
 /// The name of the IBC Eureka Interface (required by voyager)
 pub const SOL_IBC_EUREKA_INTERFACE: &str = "solidity-ibc-eureka";
 
+/// The name of the IBC Eureka Interface for Solidity light clients that are updated with
+/// protobuf-encoded Tendermint headers (required by voyager)
+pub const SOL_IBC_EUREKA_PROTOBUF_HEADER_INTERFACE: &str = "solidity-ibc-eureka/protobuf-header";
+
+/// The name of the ibc-go interface for light clients hosted by 08-wasm (required by voyager)
+pub const IBC_GO_08_WASM_INTERFACE: &str = "ibc-go-v8/08-wasm";
+
 /// The name of the sp1-ics07-tendermint client (required by voyager)
 pub const SP1_ICS07_CLIENT_TYPE: &str = "sp1-ics07-tendermint";
 
+/// The name of the mock client (required by voyager)
+pub const MOCK_CLIENT_TYPE: &str = "mock";
+
 pub mod msg;
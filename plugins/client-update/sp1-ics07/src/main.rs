@@ -6,12 +6,16 @@ mod call;
 mod callback;
 mod data;
 
-use std::{collections::VecDeque, env, str::FromStr};
+#[cfg(test)]
+mod tests;
+
+use std::{collections::VecDeque, env, str::FromStr, time::Instant};
 
 use alloy_sol_types::SolValue;
 use call::{FetchSP1Proof, FetchUpdate, ModuleCall};
 use callback::ModuleCallback;
 use data::{ModuleData, ProveResponse};
+use ibc_eureka_telemetry::{health::health, metrics::metrics, server::TelemetryConfig};
 use jsonrpsee::{
     core::{async_trait, RpcResult},
     Extensions,
@@ -27,7 +31,7 @@ use sp1_ics07_tendermint_solidity::{
 };
 use sp1_ics07_tendermint_utils::{light_block::LightBlockExt, rpc::TendermintRpcExt};
 use sp1_sdk::HashableKey;
-use tendermint_rpc::{HttpClient, Url};
+use tendermint_rpc::{Client, HttpClient, Url};
 use voyager_message::{
     call::{Call, WaitForHeight},
     core::ChainId,
@@ -38,6 +42,18 @@ use voyager_message::{
 };
 use voyager_vm::{call, data, pass::PassResult, seq, void, BoxDynError, Op, Visit};
 
+/// The module name used as a metric label
+const METRICS_MODULE: &str = "client-update-sp1-ics07";
+
+/// The name of the Tendermint RPC in the health report
+const TENDERMINT_RPC_DEPENDENCY: &str = "tendermint_rpc";
+
+/// The name of the SP1 prover in the health report
+const SP1_PROVER_DEPENDENCY: &str = "sp1_prover";
+
+/// The RPC of the SP1 prover network used by the SP1 SDK if `PROVER_NETWORK_RPC` is not set
+const DEFAULT_PROVER_NETWORK_RPC: &str = "https://rpc.succinct.xyz/";
+
 /// The configuration for the SP1 ICS07 Light Client Update Plugin
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Config {
@@ -63,6 +79,11 @@ pub struct Config {
     /// Proof type
     /// Should be one of "groth16", "plonk"
     pub proof_type: String,
+
+    /// The telemetry endpoint, serving metrics and health checks. It is disabled if this is not
+    /// set.
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
 }
 
 /// The SP1 ICS07 Light Client Update Plugin
@@ -95,6 +116,10 @@ impl Plugin for Module {
     type Cmd = DefaultCmd;
 
     async fn new(config: Self::Config) -> Result<Self, BoxDynError> {
+        if let Some(telemetry_config) = &config.telemetry {
+            ibc_eureka_telemetry::server::spawn(telemetry_config).await?;
+        }
+
         let tm_client = HttpClient::new(Url::from_str(&config.tm_rpc_url)?)?;
 
         let tm_chain_id = tm_client.get_light_block(None).await?.chain_id()?;
@@ -106,9 +131,22 @@ impl Plugin for Module {
             .into());
         }
 
+        let check_client = tm_client.clone();
+        health().register_check(TENDERMINT_RPC_DEPENDENCY, move || {
+            let tm_client = check_client.clone();
+            async move {
+                tm_client
+                    .status()
+                    .await
+                    .map(|status| Some(status.sync_info.latest_block_height.value()))
+                    .map_err(|err| err.to_string())
+            }
+        });
+
         // NOTE: SP1 SDK only supports initializing through environment variables
         env::set_var("SP1_PROVER", &config.sp1_prover);
         env::set_var("SP1_PRIVATE_KEY", &config.sp1_private_key);
+        register_prover_check(&config.sp1_prover);
 
         let proof_type = match config.proof_type.as_str() {
             "groth16" => SupportedProofType::Groth16,
@@ -148,20 +186,42 @@ impl PluginServer<ModuleCall, ModuleCallback> for Module {
                 update_to,
                 update_from,
             }) => {
+                let start = Instant::now();
                 let trusted_light_block = self
                     .tm_client
                     .get_light_block(Some(update_from.height().try_into().unwrap()))
-                    .await
-                    .unwrap();
+                    .await;
+                metrics().record_rpc_call(
+                    "tendermint",
+                    "light_block",
+                    trusted_light_block.is_ok(),
+                    start.elapsed(),
+                );
+                health().record(TENDERMINT_RPC_DEPENDENCY, &trusted_light_block);
+                let trusted_light_block = trusted_light_block.unwrap();
 
                 // Get trusted consensus state from the trusted light block.
                 let trusted_consensus_state = trusted_light_block.to_consensus_state();
 
+                let start = Instant::now();
                 let target_light_block = self
                     .tm_client
                     .get_light_block(Some(update_to.height().try_into().unwrap()))
-                    .await
-                    .unwrap();
+                    .await;
+                metrics().record_rpc_call(
+                    "tendermint",
+                    "light_block",
+                    target_light_block.is_ok(),
+                    start.elapsed(),
+                );
+                health().record(TENDERMINT_RPC_DEPENDENCY, &target_light_block);
+                let target_light_block = target_light_block.unwrap();
+                metrics().record_latest_height(
+                    METRICS_MODULE,
+                    self.chain_id.as_str(),
+                    update_to.height(),
+                );
+                health().record_latest_height(self.chain_id.as_str(), update_to.height());
 
                 // Get the proposed header from the target light block.
                 let proposed_header = target_light_block.into_header(&trusted_light_block);
@@ -185,6 +245,7 @@ impl PluginServer<ModuleCall, ModuleCallback> for Module {
                 proposed_header,
             }) => {
                 let trusted_consensus_state = trusted_consensus_state.into();
+                let start = Instant::now();
                 let proof = self.client_update_prover.generate_proof(
                     &self.to_client_state(),
                     &trusted_consensus_state,
@@ -194,6 +255,16 @@ impl PluginServer<ModuleCall, ModuleCallback> for Module {
                         .unwrap()
                         .as_secs(),
                 );
+                metrics().record_proof_generation(
+                    METRICS_MODULE,
+                    match self.proof_type {
+                        SupportedProofType::Groth16 => "groth16",
+                        SupportedProofType::Plonk => "plonk",
+                    },
+                    start.elapsed(),
+                );
+                // NOTE: proof generation panics on failure, so only successes are recorded
+                health().record_success(SP1_PROVER_DEPENDENCY);
 
                 let sp1_proof = SP1Proof::new(
                     &self.client_update_prover.vkey.bytes32(),
@@ -252,6 +323,64 @@ impl PluginServer<ModuleCall, ModuleCallback> for Module {
     }
 }
 
+/// Register the readiness check of the SP1 prover. The prover network is probed over HTTP, and
+/// the local prover needs docker to wrap proofs into groth16 or plonk proofs.
+fn register_prover_check(sp1_prover: &str) {
+    match sp1_prover {
+        "network" => {
+            let network_rpc = env::var("PROVER_NETWORK_RPC")
+                .unwrap_or_else(|_| DEFAULT_PROVER_NETWORK_RPC.to_string());
+            let client = reqwest::Client::new();
+
+            health().register_check(SP1_PROVER_DEPENDENCY, move || {
+                let (client, network_rpc) = (client.clone(), network_rpc.clone());
+                async move { probe_prover_network(&client, &network_rpc).await }
+            });
+        }
+        "local" => health().register_check(SP1_PROVER_DEPENDENCY, || async {
+            let output = tokio::process::Command::new("docker")
+                .arg("info")
+                .kill_on_drop(true)
+                .output()
+                .await
+                .map_err(|err| format!("unable to run docker: {err}"))?;
+
+            if output.status.success() {
+                Ok(None)
+            } else {
+                Err(format!(
+                    "docker is not available: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            }
+        }),
+        // the mock prover does not depend on anything
+        _ => health().register_check(SP1_PROVER_DEPENDENCY, || async { Ok(None) }),
+    }
+}
+
+/// Check that the SP1 prover network at `network_rpc` is reachable and not failing. Any
+/// response other than a server error counts, as the RPC does not serve a health endpoint.
+async fn probe_prover_network(
+    client: &reqwest::Client,
+    network_rpc: &str,
+) -> Result<Option<u64>, String> {
+    let response = client
+        .get(network_rpc)
+        .send()
+        .await
+        .map_err(|err| format!("prover network is unreachable: {err}"))?;
+
+    if response.status().is_server_error() {
+        return Err(format!(
+            "prover network responded with {}",
+            response.status()
+        ));
+    }
+
+    Ok(None)
+}
+
 impl Module {
     fn plugin_name(&self) -> String {
         plugin_name(&self.chain_id)
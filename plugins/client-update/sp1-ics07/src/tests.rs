@@ -0,0 +1,41 @@
+//! Tests of the readiness check of the SP1 prover against a stand-in prover network
+
+use ibc_eureka_test_utils::stand_in::StandIn;
+use serde_json::json;
+
+use crate::probe_prover_network;
+
+#[tokio::test]
+async fn prover_network_is_ready_if_it_responds() {
+    // the prover network only serves its RPC methods, and not found responses are expected
+    for status in [200, 404, 405] {
+        let network = StandIn::serve(move |_| (status, json!({ "code": "not_found" }))).await;
+
+        assert_eq!(
+            probe_prover_network(&reqwest::Client::new(), &network.url()).await,
+            Ok(None)
+        );
+        assert_eq!(network.requests().len(), 1);
+    }
+}
+
+#[tokio::test]
+async fn prover_network_is_not_ready_on_server_errors() {
+    let network = StandIn::serve(|_| (503, json!({ "code": "unavailable" }))).await;
+
+    let err = probe_prover_network(&reqwest::Client::new(), &network.url())
+        .await
+        .unwrap_err();
+
+    assert!(err.contains("503"), "{err}");
+}
+
+#[tokio::test]
+async fn prover_network_is_not_ready_if_unreachable() {
+    // nothing listens on the discard port
+    let err = probe_prover_network(&reqwest::Client::new(), "http://127.0.0.1:9/")
+        .await
+        .unwrap_err();
+
+    assert!(err.contains("unreachable"), "{err}");
+}
@@ -24,3 +24,17 @@ pub enum TxSubmitError {
     #[error("eth rpc call failed: {0}")]
     EthRpcError(#[from] RpcError<TransportErrorKind>),
 }
+
+impl TxSubmitError {
+    /// A short, stable name of the error, used as a metric label.
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Error(_) => "contract",
+            Self::PendingTransactionError(_) => "pending_transaction",
+            Self::OutOfGas => "out_of_gas",
+            Self::EmptyRevert(_) => "empty_revert",
+            Self::GasPriceTooHigh { .. } => "gas_price_too_high",
+            Self::EthRpcError(_) => "rpc",
+        }
+    }
+}
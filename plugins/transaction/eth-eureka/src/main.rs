@@ -17,7 +17,13 @@ use alloy::{
 use call::ModuleCall;
 use callback::ModuleCallback;
 use error::TxSubmitError;
+use ibc_eureka_rpc::{
+    config::{Endpoints, FailoverConfig, QuorumConfig},
+    execution::{FailoverTransport, EXECUTION_RPC_DEPENDENCY},
+    retry::{RateLimitConfig, RetryConfig},
+};
 use ibc_eureka_solidity::{ics02::client::clientInstance, ics26::router::routerInstance};
+use ibc_eureka_telemetry::{health::health, metrics::metrics, server::TelemetryConfig};
 use ibc_eureka_types::msg::IbcEurekaVoyagerMessage;
 use jsonrpsee::{
     core::{async_trait, RpcResult},
@@ -39,6 +45,9 @@ mod callback;
 mod data;
 mod error;
 
+/// The module name used as a metric label
+const METRICS_MODULE: &str = "transaction-eth-eureka";
+
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
     run_plugin_server::<Module>().await;
@@ -53,8 +62,21 @@ pub struct Config {
     /// The address of the `IBCHandler` smart contract.
     pub ics26_router_address: String,
 
-    /// The RPC endpoint for the execution chain.
-    pub eth_rpc_api: String,
+    /// The RPC endpoint(s) for the execution chain, in order of preference.
+    pub eth_rpc_api: Endpoints,
+    /// When an RPC endpoint is considered unhealthy and failed over from.
+    #[serde(default)]
+    pub rpc_failover: FailoverConfig,
+    /// Require matching responses from multiple endpoints for critical reads.
+    #[serde(default)]
+    pub rpc_quorum: Option<QuorumConfig>,
+    /// How failed RPC requests are retried.
+    #[serde(default)]
+    pub rpc_retry: RetryConfig,
+    /// The request rate limit of each RPC endpoint. Requests are not rate limited if this is
+    /// not set.
+    #[serde(default)]
+    pub rpc_rate_limit: Option<RateLimitConfig>,
 
     /// The private key for the Ethereum account.
     // TODO: Use a more secure way to store the private key.
@@ -63,6 +85,11 @@ pub struct Config {
     /// The maximum gas price for any submitted transaction.
     #[serde(default)]
     pub max_gas_price: Option<u128>,
+
+    /// The telemetry endpoint, serving metrics and health checks. It is disabled if this is not
+    /// set.
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
 }
 
 /// The Ethereum IBC Eureka transaction module
@@ -95,6 +122,10 @@ impl Plugin for Module {
     type Cmd = DefaultCmd;
 
     async fn new(config: Self::Config) -> Result<Self, BoxDynError> {
+        if let Some(telemetry_config) = &config.telemetry {
+            ibc_eureka_telemetry::server::spawn(telemetry_config).await?;
+        }
+
         let wallet = EthereumWallet::from(
             config
                 .private_key
@@ -103,10 +134,13 @@ impl Plugin for Module {
                 .parse::<PrivateKeySigner>()?,
         );
 
-        let provider = ProviderBuilder::new()
-            .wallet(wallet.clone())
-            .on_builtin(&config.eth_rpc_api)
-            .await?;
+        let provider = ProviderBuilder::new().wallet(wallet.clone()).on_client(
+            FailoverTransport::connect(&config.eth_rpc_api, config.rpc_failover, config.rpc_quorum)
+                .await?
+                .with_retry(config.rpc_retry)
+                .with_rate_limit(config.rpc_rate_limit.as_ref())
+                .into_client(),
+        );
 
         let raw_chain_id = provider.get_chain_id().await?;
         let chain_id = ChainId::new(raw_chain_id.to_string());
@@ -119,6 +153,18 @@ impl Plugin for Module {
             .into());
         }
 
+        let check_provider = provider.clone();
+        health().register_check(EXECUTION_RPC_DEPENDENCY, move || {
+            let provider = check_provider.clone();
+            async move {
+                provider
+                    .get_block_number()
+                    .await
+                    .map(Some)
+                    .map_err(|err| err.to_string())
+            }
+        });
+
         let ics26_router =
             routerInstance::new(Address::from_str(&config.ics26_router_address)?, provider);
 
@@ -160,6 +206,9 @@ impl PluginServer<ModuleCall, ModuleCallback> for Module {
         match msg {
             ModuleCall::SubmitCall(msg) => {
                 self.submit_tx(msg).await.map_err(|err| {
+                    metrics().record_tx_submission(METRICS_MODULE, "failed", None);
+                    metrics().record_error(METRICS_MODULE, err.kind());
+
                     ErrorObject::owned(-1, ErrorReporter(err).to_string(), None::<()>)
                 })?;
 
@@ -206,14 +255,28 @@ impl Module {
 
                 // TODO: Add retry logic here, similar to
                 // https://github.com/unionlabs/union/blob/18c86b4ff81408d31bec998f5d23bc1b03c9fda3/voyager/plugins/transaction/ethereum/src/main.rs#L351
-                let _ = ics02_client
+                let receipt = ics02_client
                     .updateClient(update_msg.client_id, update_msg.msg.into())
                     .send()
                     .await?
-                    .watch()
+                    .get_receipt()
                     .await?;
 
-                info!("client updated successfully");
+                let status = if receipt.status() {
+                    "included"
+                } else {
+                    "reverted"
+                };
+                metrics().record_tx_submission(
+                    METRICS_MODULE,
+                    status,
+                    Some(u64::try_from(receipt.gas_used).unwrap_or(u64::MAX)),
+                );
+
+                info!(
+                    gas_used = receipt.gas_used,
+                    status, "client update submitted"
+                );
 
                 Ok(())
             }